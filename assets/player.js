@@ -0,0 +1,49 @@
+const video = document.querySelector(".player video");
+const progressUrl = "/api/progress/" + video.dataset.key;
+let lastReport = 0;
+
+// resumes where the video was left off, unless it was finished
+fetch(progressUrl)
+    .then((response) => (response.ok ? response.json() : null))
+    .then(function (progress) {
+        if (!progress || progress.watched || progress.position <= 0) {
+            return;
+        }
+        const resume = function () {
+            video.currentTime = progress.position;
+        };
+        if (video.readyState >= 1) {
+            resume();
+        } else {
+            video.addEventListener("loadedmetadata", resume, { once: true });
+        }
+    });
+
+function report(watched) {
+    if (!video.duration) {
+        return;
+    }
+    lastReport = Date.now();
+    const update = { position: video.currentTime, duration: video.duration };
+    if (watched !== undefined) {
+        update.watched = watched;
+    }
+    fetch(progressUrl, {
+        method: "POST",
+        headers: { "Content-Type": "application/json" },
+        body: JSON.stringify(update),
+        keepalive: true,
+    });
+}
+
+video.addEventListener("timeupdate", function () {
+    if (Date.now() - lastReport > 10000) {
+        report();
+    }
+});
+video.addEventListener("pause", function () {
+    report();
+});
+video.addEventListener("ended", function () {
+    report(true);
+});
@@ -2,9 +2,76 @@ const cards = document.querySelectorAll(".card");
 cards.forEach(function (card) {
     const expand = card.querySelector(".card-expand");
     card.addEventListener("click", function (event) {
-        if (event.target.tagName !== "IMG") {
+        // season sections fold on their own
+        if (event.target.tagName !== "IMG" && !event.target.closest("summary")) {
             expand.classList.toggle("show");
             card.classList.toggle("expanded");
         }
     });
 });
+
+// watch progress of the viewer, keyed by '{id}' for movies and '{id}/{season}/{episode}' for episodes
+fetch("/api/progress")
+    .then((response) => (response.ok ? response.json() : {}))
+    .then(function (progress) {
+        cards.forEach(function (card) {
+            const episodes = card.querySelectorAll("li[data-key]");
+            let keys = [];
+            if (episodes.length > 0) {
+                episodes.forEach(function (episode) {
+                    keys.push(episode.dataset.key);
+                    if (progress[episode.dataset.key]?.watched) {
+                        episode.classList.add("watched");
+                    }
+                });
+            } else if (card.querySelector("a[href^='/watch/']")) {
+                keys = [card.dataset.id];
+            } else {
+                return;
+            }
+
+            const watched = keys.filter((key) => progress[key]?.watched).length;
+            const started = keys.filter((key) => progress[key]).length;
+            const badge = document.createElement("span");
+            if (watched === keys.length) {
+                badge.className = "badge badge-watched";
+                badge.textContent = "watched";
+            } else if (started > 0) {
+                badge.className = "badge badge-in-progress";
+                badge.textContent = "in progress";
+            } else {
+                badge.className = "badge badge-unwatched";
+                badge.textContent = "unwatched";
+            }
+            card.querySelector(".card-header-box-title").appendChild(badge);
+        });
+
+        const row = document.querySelector(".continue-watching-row");
+        Object.entries(progress)
+            .filter(([, entry]) => !entry.watched && entry.position > 0)
+            .sort(([, a], [, b]) => b.updated - a.updated)
+            .slice(0, 10)
+            .forEach(function ([key, entry]) {
+                const [id, season, episode] = key.split("/");
+                const card = document.querySelector(`.card[data-id="${id}"]`);
+                if (!card) {
+                    return;
+                }
+                const item = document.createElement("a");
+                item.className = "continue-watching-item";
+                item.href = "/watch/" + key;
+                const thumbnail = document.createElement("img");
+                thumbnail.src = card.querySelector(".card-header-thumbnail img").src;
+                const title = document.createElement("span");
+                title.textContent = card.querySelector("h2").textContent;
+                const details = document.createElement("span");
+                const percent = entry.duration > 0 ? Math.round((100 * entry.position) / entry.duration) : 0;
+                details.textContent =
+                    (season ? `S${season.padStart(2, "0")}E${episode.padStart(2, "0")} • ` : "") + `${percent}%`;
+                item.append(thumbnail, title, details);
+                row.appendChild(item);
+            });
+        if (row.children.length > 0) {
+            row.parentElement.hidden = false;
+        }
+    });
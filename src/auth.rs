@@ -0,0 +1,108 @@
+// Imports
+use argon2::{
+    password_hash::{rand_core::OsRng, rand_core::RngCore, SaltString},
+    Argon2, PasswordHash, PasswordHasher, PasswordVerifier,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::Instant,
+};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct User {
+    pub name: String,
+    // argon2 hash in the PHC string format
+    pub password_hash: String,
+}
+
+// verified against when the username is unknown, so that failed logins take the same time
+static DUMMY_HASH: &str =
+    "$argon2id$v=19$m=19456,t=2,p=1$c2lsdnVzc2lsdnVz$0mL5wMUDTzsDmEP4yNtPvJIrfy+C3K8ZhWR9cOCjKtY";
+
+pub fn hash_password(password: &str) -> eyre::Result<String> {
+    let salt = SaltString::generate(&mut OsRng);
+    Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .map(|hash| hash.to_string())
+        .map_err(|err| eyre::eyre!("Failed to hash password, '{err}'"))
+}
+
+/// Returns the name of the user if the credentials are valid
+pub fn authenticate<'a>(users: &'a [User], name: &str, password: &str) -> Option<&'a str> {
+    let user = users.iter().find(|user| user.name == name);
+    let hash = user.map_or(DUMMY_HASH, |user| user.password_hash.as_str());
+    let verified = PasswordHash::new(hash).is_ok_and(|hash| {
+        Argon2::default()
+            .verify_password(password.as_bytes(), &hash)
+            .is_ok()
+    });
+    user.filter(|_| verified).map(|user| user.name.as_str())
+}
+
+/// Random hex token, used for sessions and to tell anonymous clients apart
+pub fn random_token() -> String {
+    let mut bytes = [0_u8; 32];
+    OsRng.fill_bytes(&mut bytes);
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+struct Session {
+    user: String,
+    expires: Instant,
+}
+
+// In-memory store of the active sessions, keyed by the session token
+#[derive(Clone, Default)]
+pub struct Sessions(Arc<Mutex<HashMap<String, Session>>>);
+
+impl Sessions {
+    /// Creates a new session for the user and returns its token
+    pub fn create(&self, user: &str) -> String {
+        let token = random_token();
+
+        let mut sessions = self.0.lock().unwrap_or_else(|err| err.into_inner());
+        let now = Instant::now();
+        sessions.retain(|_, session| session.expires > now);
+        sessions.insert(
+            token.clone(),
+            Session {
+                user: user.to_owned(),
+                expires: now + crate::config::get().session_lifetime,
+            },
+        );
+        token
+    }
+
+    /// Returns the user of the session if it exists and hasn't expired
+    pub fn get(&self, token: &str) -> Option<String> {
+        let sessions = self.0.lock().unwrap_or_else(|err| err.into_inner());
+        sessions
+            .get(token)
+            .filter(|session| session.expires > Instant::now())
+            .map(|session| session.user.clone())
+    }
+
+    pub fn remove(&self, token: &str) {
+        let mut sessions = self.0.lock().unwrap_or_else(|err| err.into_inner());
+        sessions.remove(token);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn password_verification() {
+        let users = vec![User {
+            name: "alice".to_string(),
+            password_hash: hash_password("hunter2").unwrap(),
+        }];
+        assert_eq!(authenticate(&users, "alice", "hunter2"), Some("alice"));
+        assert_eq!(authenticate(&users, "alice", "hunter3"), None);
+        assert_eq!(authenticate(&users, "bob", "hunter2"), None);
+        assert!(PasswordHash::new(DUMMY_HASH).is_ok());
+    }
+}
@@ -1,57 +1,51 @@
 use super::*;
+use index::{Index, Scan};
 
 pub struct Cards(Vec<Card>);
 
-static STYLE: &str = include_str!("../../assets/style.css");
+pub static STYLE: &str = include_str!("../../assets/style.css");
 static SCRIPT: &str = include_str!("../../assets/script.js");
 
 impl Cards {
     pub fn load() -> eyre::Result<Self> {
+        Ok(Self::scan(false)?.0)
+    }
+
+    /// Loads the cards through the library index, also returns how many had to be read
+    /// from the filesystem
+    pub fn scan(full: bool) -> eyre::Result<(Self, usize)> {
         let path = crate::config::get()
             .target_dir
             .to_owned()
             .ok_or_eyre("No target path set, use the init subcommand")?;
 
-        let cards: Vec<Card> = std::fs::read_dir(&path)?
-            .filter_map(|dir| {
-                let dir = match dir {
-                    Ok(dir) => dir.path(),
-                    Err(err) => {
-                        warn!("{err}");
-                        return None;
-                    }
-                };
-                if get_filestem(&dir).starts_with('.') {
-                    return None;
-                }
-                if dir.is_dir() {
-                    match Card::from_path(&path, &dir) {
-                        Ok(card) => Some(card),
-                        Err(err) => {
-                            warn!("{} - {err}", dir.display());
-                            None
-                        }
-                    }
-                } else {
-                    None
-                }
-            })
-            .sorted_by(|a, b| a.get_title().cmp(b.get_title()))
-            .collect();
+        let Scan {
+            mut cards,
+            rescanned,
+        } = Index::scan(&path, full)?;
+        cards.sort_by(|a, b| a.get_title().cmp(b.get_title()));
+
+        Ok((Self(cards), rescanned))
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, Card> {
+        self.0.iter()
+    }
 
-        Ok(Self(cards))
+    pub fn get(&self, id: &str) -> Option<&Card> {
+        self.0.iter().find(|card| card.get_id() == id)
     }
 
-    pub fn generate_static_html_page(self) -> String {
+    pub fn generate_static_html_page(&self) -> String {
         let mut left_column = String::new();
         let mut right_column = String::new();
 
-        for (idx, card) in self.0.into_iter().enumerate() {
+        for (idx, card) in self.0.iter().enumerate() {
             if idx % 2 == 0 {
-                left_column.push_str(&card.into_html_string());
+                left_column.push_str(&card.to_html_string());
                 left_column.push('\n');
             } else {
-                right_column.push_str(&card.into_html_string());
+                right_column.push_str(&card.to_html_string());
                 right_column.push('\n');
             }
         }
@@ -72,6 +66,11 @@ impl Cards {
                 <body>
                     <div class=\"page-header\">
                         <img src=\"/res/.assets/icon.svg\" />
+                        {}
+                    </div>
+                    <div class=\"continue-watching\" hidden>
+                        <h3>continue watching</h3>
+                        <div class=\"continue-watching-row\"></div>
                     </div>
                     <div class=\"card-row\">
                         <div class=\"card-column\">
@@ -88,9 +87,66 @@ impl Cards {
             </html>
             ",
             STYLE,
+            if crate::config::get().users.is_empty() {
+                ""
+            } else {
+                "<form class=\"logout\" method=\"post\" action=\"/logout\"><button type=\"submit\">log out</button></form>"
+            },
             left_column,
             right_column,
             SCRIPT,
         }
     }
 }
+
+/// Lists the directories of every card located in the target directory
+pub fn card_dirs(base: &Path) -> eyre::Result<Vec<PathBuf>> {
+    Ok(walk(base)?.0)
+}
+
+/// Lists the directories of every card and every category directory, the target
+/// directory included
+pub(super) fn walk(base: &Path) -> eyre::Result<(Vec<PathBuf>, Vec<PathBuf>)> {
+    let mut dirs: Vec<PathBuf> = Vec::new();
+    let mut categories: Vec<PathBuf> = Vec::new();
+    find_dirs(base, &mut dirs, &mut categories)?;
+    Ok((dirs, categories))
+}
+
+// Walks a category directory, every sub-directory is either a card or a nested category
+fn find_dirs(
+    dir: &Path,
+    dirs: &mut Vec<PathBuf>,
+    categories: &mut Vec<PathBuf>,
+) -> eyre::Result<()> {
+    categories.push(dir.to_owned());
+    for entry in std::fs::read_dir(dir)? {
+        let entry = match entry {
+            Ok(entry) => entry.path(),
+            Err(err) => {
+                warn!("{err}");
+                continue;
+            }
+        };
+        if get_filename(&entry).starts_with('.') {
+            continue;
+        }
+        // only the target directory may hold loose files, other directories with files are cards
+        if !entry.is_dir() {
+            continue;
+        }
+
+        let result = Card::is_card_dir(&entry).and_then(|is_card| {
+            if is_card {
+                dirs.push(entry.clone());
+                Ok(())
+            } else {
+                find_dirs(&entry, dirs, categories)
+            }
+        });
+        if let Err(err) = result {
+            warn!("{} - {err}", entry.display());
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,320 @@
+use super::*;
+use std::collections::{HashMap, HashSet};
+use std::time::SystemTime;
+use tracing::debug;
+
+// bumped whenever the stored cards change shape, older indexes are rebuilt from scratch
+const VERSION: u32 = 4;
+
+/// Cards of the previous scan along with the modification times they were built from,
+/// persisted in the data directory so that unchanged cards aren't read again
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Index {
+    version: u32,
+    target_dir: PathBuf,
+    // directories holding cards, their list is reused as long as none of them changed
+    categories: Vec<Stamp>,
+    cards: Vec<Entry>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Entry {
+    dir: PathBuf,
+    // directories and metadata files of the card
+    stamps: Vec<Stamp>,
+    card: Card,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Stamp {
+    path: PathBuf,
+    // None if the path doesn't exist
+    modified: Option<SystemTime>,
+}
+
+impl Stamp {
+    fn new(path: &Path) -> Self {
+        Self {
+            path: path.to_owned(),
+            modified: path
+                .metadata()
+                .and_then(|metadata| metadata.modified())
+                .ok(),
+        }
+    }
+
+    fn is_current(&self) -> bool {
+        Self::new(&self.path) == *self
+    }
+}
+
+/// Outcome of a scan of the target directory
+pub struct Scan {
+    pub cards: Vec<Card>,
+    // cards that had to be read from the filesystem
+    pub rescanned: usize,
+}
+
+impl Index {
+    fn fp() -> Option<PathBuf> {
+        crate::dirs::try_get().map(|dirs| dirs.data_local_dir().join("library_index.json"))
+    }
+
+    // None if there's no usable index for this target directory
+    fn read(base: &Path) -> Option<Self> {
+        Self::read_from_file(&Self::fp()?, base)
+    }
+
+    fn read_from_file(fp: &Path, base: &Path) -> Option<Self> {
+        let bytes = std::fs::read(fp).ok()?;
+        let index: Self = serde_json::from_slice(&bytes)
+            .inspect_err(|err| warn!("Failed to parse the library index, '{err}'"))
+            .ok()?;
+        (index.version == VERSION && index.target_dir == base).then_some(index)
+    }
+
+    fn save(&self) -> eyre::Result<()> {
+        match Self::fp() {
+            Some(fp) => self.save_to_file(&fp),
+            None => Ok(()),
+        }
+    }
+
+    fn save_to_file(&self, fp: &Path) -> eyre::Result<()> {
+        if let Some(dir) = fp.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        crate::utils::write_atomic(fp, &serde_json::to_vec(self)?)
+    }
+
+    /// Builds the cards of the target directory, reusing the indexed cards whose directories
+    /// and metadata files haven't been modified since, unless a full scan is requested.
+    /// Videos replaced in place under the same name are only picked up by a full scan
+    pub fn scan(base: &Path, full: bool) -> eyre::Result<Scan> {
+        let index = match full {
+            true => None,
+            false => Self::read(base),
+        };
+        let (categories, mut previous) = match index {
+            Some(index) => (
+                Some(index.categories),
+                index
+                    .cards
+                    .into_iter()
+                    .map(|entry| (entry.dir.clone(), entry))
+                    .collect::<HashMap<_, _>>(),
+            ),
+            None => (None, HashMap::new()),
+        };
+
+        let unchanged: HashSet<PathBuf> = previous
+            .values()
+            .filter(|entry| entry.stamps.iter().all(Stamp::is_current))
+            .map(|entry| entry.dir.clone())
+            .collect();
+
+        // a modified card directory may have become a category, the library is walked again then
+        let known_dirs = categories
+            .filter(|categories| categories.iter().all(Stamp::is_current))
+            .filter(|_| {
+                previous
+                    .keys()
+                    .filter(|dir| !unchanged.contains(*dir))
+                    .all(|dir| Card::is_card_dir(dir).unwrap_or(false))
+            })
+            .map(|categories| (previous.keys().cloned().collect_vec(), categories));
+        let walked = known_dirs.is_none();
+        let (dirs, categories) = match known_dirs {
+            Some(known_dirs) => known_dirs,
+            None => {
+                debug!("Walking the target directory");
+                let (dirs, categories) = cards::walk(base)?;
+                (dirs, categories.iter().map(|dir| Stamp::new(dir)).collect())
+            }
+        };
+
+        let mut rescanned = 0;
+        let mut entries = Vec::with_capacity(dirs.len());
+        for dir in dirs {
+            if let Some(entry) = previous.remove(&dir).filter(|_| unchanged.contains(&dir)) {
+                entries.push(entry);
+                continue;
+            }
+            rescanned += 1;
+            // stamped first, changes made while reading are picked up by the next scan
+            let stamps = stamps(&dir);
+            match Card::from_path(base, &dir) {
+                Ok(card) => entries.push(Entry { dir, stamps, card }),
+                Err(err) => warn!("{} - {err}", dir.display()),
+            }
+        }
+
+        let mut index = Self {
+            version: VERSION,
+            target_dir: base.to_owned(),
+            categories,
+            cards: entries,
+        };
+        // cards left in the previous index were removed
+        if rescanned > 0 || walked || !previous.is_empty() {
+            if let Err(err) = index.save() {
+                warn!("Failed to save the library index, '{err}'");
+            }
+        }
+        // the probe cache only saw the rescanned videos unless everything was rescanned
+        if let Err(err) = crate::probe::save_cache(rescanned == index.cards.len()) {
+            warn!("Failed to save the probe cache, '{err}'");
+        }
+
+        Ok(Scan {
+            cards: std::mem::take(&mut index.cards)
+                .into_iter()
+                .map(|entry| entry.card)
+                .collect(),
+            rescanned,
+        })
+    }
+}
+
+// Every directory of a card, which covers added, removed and renamed files, along with the
+// metadata files as they're usually edited in place. Hidden directories aren't part of the card
+fn stamps(dir: &Path) -> Vec<Stamp> {
+    let mut stamps = vec![Stamp::new(dir)];
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return stamps;
+    };
+    for fp in entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+    {
+        let is_hidden = get_filename(&fp).starts_with('.');
+        if fp.is_dir() {
+            if !is_hidden {
+                stamps.extend(self::stamps(&fp));
+            }
+        } else if is_hidden || get_extension(&fp).eq_ignore_ascii_case("nfo") {
+            stamps.push(Stamp::new(&fp));
+        }
+    }
+    stamps
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::File;
+    use std::time::Duration;
+
+    #[test]
+    fn stamps_detect_changes() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        std::fs::create_dir_all(dir.join("Season 01")).unwrap();
+        std::fs::create_dir_all(dir.join(".hidden")).unwrap();
+        std::fs::write(dir.join(".title"), "Title").unwrap();
+        std::fs::write(dir.join("tvshow.nfo"), "<tvshow />").unwrap();
+        std::fs::write(dir.join("Season 01/S01E01.mkv"), "").unwrap();
+        std::fs::write(dir.join(".hidden/notes"), "").unwrap();
+
+        // the directories and metadata files, videos are covered by their directory
+        let stamps = stamps(dir);
+        let mut paths = stamps.iter().map(|stamp| stamp.path.clone()).collect_vec();
+        paths.sort();
+        assert_eq!(
+            paths,
+            [
+                dir.to_owned(),
+                dir.join(".title"),
+                dir.join("Season 01"),
+                dir.join("tvshow.nfo"),
+            ]
+        );
+        assert!(stamps.iter().all(Stamp::is_current));
+
+        // in place edits don't modify the directory, the metadata files themselves are stamped
+        for fp in [".title", "tvshow.nfo"] {
+            let stamps = self::stamps(dir);
+            let file = File::options().write(true).open(dir.join(fp)).unwrap();
+            let modified = file.metadata().unwrap().modified().unwrap();
+            file.set_modified(modified + Duration::from_secs(60))
+                .unwrap();
+            assert!(!stamps.iter().all(Stamp::is_current));
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let tmp = tempfile::tempdir().unwrap();
+        let base = tmp.path();
+        for (fp, content) in [
+            ("Amélie (2001)/Amélie.mkv", ""),
+            ("Amélie (2001)/Amélie.fr.srt", ""),
+            ("Amélie (2001)/.thumbnail.jpg", ""),
+            (
+                "Amélie (2001)/.title",
+                "Le Fabuleux Destin d'Amélie Poulain",
+            ),
+            ("Show #1/Season 01/S01E01.mkv", ""),
+            ("Show #1/Season 01/S01E01.en.srt", ""),
+            ("Show #1/Season 02/S02E01.mkv", ""),
+            ("Show #1/Season 02/.thumbnail.png", ""),
+            ("Docs/100% manual.pdf", "%PDF"),
+        ] {
+            let fp = base.join(fp);
+            std::fs::create_dir_all(fp.parent().unwrap()).unwrap();
+            std::fs::write(fp, content).unwrap();
+        }
+
+        let (dirs, categories) = cards::walk(base).unwrap();
+        let index = Index {
+            version: VERSION,
+            target_dir: base.to_owned(),
+            categories: categories.iter().map(|dir| Stamp::new(dir)).collect(),
+            cards: dirs
+                .into_iter()
+                .map(|dir| Entry {
+                    stamps: stamps(&dir),
+                    card: Card::from_path(base, &dir).unwrap(),
+                    dir,
+                })
+                .collect(),
+        };
+        let fp = base.join(".index/library_index.json");
+        index.save_to_file(&fp).unwrap();
+
+        let read = Index::read_from_file(&fp, base).expect("index should be readable");
+        assert_eq!(read.cards.len(), 3);
+        for (card, read) in index.cards.iter().zip(&read.cards) {
+            assert_eq!(card.dir, read.dir);
+            assert_eq!(card.stamps, read.stamps);
+            assert_eq!(
+                serde_json::to_value(&card.card).unwrap(),
+                serde_json::to_value(&read.card).unwrap()
+            );
+            assert_eq!(card.card.to_html_string(), read.card.to_html_string());
+        }
+        let card = |title: &str| {
+            read.cards
+                .iter()
+                .map(|entry| &entry.card)
+                .find(|card| card.get_title() == title)
+                .unwrap()
+        };
+        // relative paths are stored, the api turns them into urls
+        let movie = card("Le Fabuleux Destin d'Amélie Poulain");
+        let stored = serde_json::to_string(movie).unwrap();
+        assert!(stored.contains("\"Amélie (2001)/.thumbnail.jpg\""));
+        assert!(!stored.contains("/res/"));
+        assert!(serde_json::to_string(&movie.to_api())
+            .unwrap()
+            .contains("\"/res/Am%C3%A9lie%20%282001%29/.thumbnail.jpg\""));
+        assert!(movie.has_subtitle("Amélie (2001)/Amélie.fr.srt"));
+        assert!(card("Show #1").has_subtitle("Show #1/Season 01/S01E01.en.srt"));
+        assert!(card("Show #1").has_file("Show #1/Season 02/.thumbnail.png"));
+        assert!(card("Docs").has_file("Docs/100% manual.pdf"));
+        assert!(!card("Docs").has_file("Docs/other.pdf"));
+
+        // indexes of another target directory aren't used
+        assert!(Index::read_from_file(&fp, &base.join("Docs")).is_none());
+    }
+}
@@ -0,0 +1,231 @@
+use super::*;
+use nfo::Nfo;
+
+/// Metadata of a card as stored in the '.silvus.json' file of its directory, the legacy
+/// dot-files ('.title', '.year', ...) and then NFO files are used for the fields it doesn't set
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Info {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub subtitle: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub year: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    // path relative to the card directory
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub thumbnail: Option<String>,
+    #[serde(flatten)]
+    pub details: Details,
+    // shows only
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub episodes: Vec<EpisodeInfo>,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EpisodeInfo {
+    pub season: u8,
+    pub episode: u8,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub air_date: Option<String>,
+}
+
+impl From<crate::metadata::EpisodeMetadata> for EpisodeInfo {
+    fn from(episode: crate::metadata::EpisodeMetadata) -> Self {
+        Self {
+            season: episode.season,
+            episode: episode.episode,
+            title: episode.title,
+            description: episode.description,
+            air_date: episode.air_date,
+        }
+    }
+}
+
+// Fields only available in '.silvus.json', also part of the api representation of the cards
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Details {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub original_title: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub genres: Vec<String>,
+    // in minutes
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub runtime: Option<u32>,
+    // out of 10
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rating: Option<f32>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+}
+
+impl Info {
+    pub const FILENAME: &'static str = ".silvus.json";
+
+    // stems of the dot-files replaced by '.silvus.json', the thumbnail is kept as it's binary
+    pub const LEGACY_STEMS: [&'static str; 6] = [
+        ".title",
+        ".subtitle",
+        ".subt",
+        ".year",
+        ".description",
+        ".descr",
+    ];
+
+    /// Loads the metadata of a card from its root dot-files
+    pub fn load(dir: &Path, dot_fps: &[PathBuf]) -> Self {
+        let mut info = match Self::read(dir) {
+            Ok(info) => info.unwrap_or_default(),
+            Err(err) => {
+                warn!(
+                    "Failed to parse '{}', '{err}'",
+                    dir.join(Self::FILENAME).display()
+                );
+                Self::default()
+            }
+        };
+        let legacy = Self::from_legacy(dir, dot_fps);
+        info.title = info.title.or(legacy.title);
+        info.subtitle = info.subtitle.or(legacy.subtitle);
+        info.year = info.year.or(legacy.year);
+        info.description = info.description.or(legacy.description);
+        info.thumbnail = info.thumbnail.or(legacy.thumbnail);
+
+        // Kodi/Jellyfin metadata, only read if something is still missing
+        let incomplete = info.title.is_none()
+            || info.year.is_none()
+            || info.description.is_none()
+            || info.thumbnail.is_none()
+            || info.details == Details::default();
+        if let Some(nfo) = incomplete.then(|| Nfo::find(dir)).flatten() {
+            let nfo = nfo.into_info(dir);
+            info.title = info.title.or(nfo.title);
+            info.year = info.year.or(nfo.year);
+            info.description = info.description.or(nfo.description);
+            info.thumbnail = info.thumbnail.or(nfo.thumbnail);
+            if info.details == Details::default() {
+                info.details = nfo.details;
+            }
+        }
+        info
+    }
+
+    /// Same as load, for directories whose files haven't been collected yet
+    pub fn load_dir(dir: &Path) -> eyre::Result<Self> {
+        let dot_fps = std::fs::read_dir(dir)?
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|fp| fp.is_file() && get_filename(fp).starts_with('.'))
+            .collect_vec();
+        Ok(Self::load(dir, &dot_fps))
+    }
+
+    /// Reads '.silvus.json', None if the card doesn't have one
+    pub fn read(dir: &Path) -> eyre::Result<Option<Self>> {
+        match std::fs::read(dir.join(Self::FILENAME)) {
+            Ok(bytes) => Ok(Some(serde_json::from_slice(&bytes)?)),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    pub fn save(&self, dir: &Path) -> eyre::Result<()> {
+        let bytes = serde_json::to_vec_pretty(self)?;
+        crate::utils::write_atomic(&dir.join(Self::FILENAME), &bytes)
+    }
+
+    /// Reads the legacy dot-files, one per field
+    pub fn from_legacy(dir: &Path, dot_fps: &[PathBuf]) -> Self {
+        let mut info = Self::default();
+        for dot_fp in dot_fps {
+            match get_filestem(dot_fp) {
+                ".title" => info.title = lazy_read_file_to_string(dot_fp),
+                ".subtitle" | ".subt" => info.subtitle = lazy_read_file_to_string(dot_fp),
+                ".year" => info.year = lazy_read_file_to_string(dot_fp),
+                ".description" | ".descr" => info.description = lazy_read_file_to_string(dot_fp),
+                ".thumbnail" => info.thumbnail = get_rel_path_string(dot_fp, dir),
+                _ => (),
+            }
+        }
+        info
+    }
+
+    pub fn episode(&self, season: u8, episode: u8) -> Option<&EpisodeInfo> {
+        self.episodes
+            .iter()
+            .find(|info| info.season == season && info.episode == episode)
+    }
+
+    /// Title to display, the folder name if none is set
+    pub fn title_or_folder_name(&self, dir: &Path) -> String {
+        self.title
+            .clone()
+            .filter(|title| !title.is_empty())
+            .unwrap_or_else(|| title_from_folder_name(dir))
+    }
+
+    /// Path of the thumbnail relative to the target directory, if it exists
+    pub fn thumbnail_rel_path(&self, base: &Path, dir: &Path) -> Option<String> {
+        let thumbnail = dir.join(self.thumbnail.as_ref()?);
+        if !thumbnail.is_file() {
+            warn!("Thumbnail '{}' doesn't exist", thumbnail.display());
+            return None;
+        }
+        get_rel_path_string(&thumbnail, base)
+    }
+}
+
+impl Details {
+    /// Line of the card body such as 'Drama, Crime • 2h22 • 9.3/10', empty if nothing is known
+    pub fn to_html_string(&self) -> String {
+        let mut parts: Vec<String> = Vec::new();
+        if !self.genres.is_empty() {
+            parts.push(html::text(&self.genres.join(", ")));
+        }
+        if let Some(runtime) = self.runtime {
+            parts.push(format!("{}h{:0>2}", runtime / 60, runtime % 60));
+        }
+        if let Some(rating) = self.rating {
+            parts.push(format!("{rating}/10"));
+        }
+        match parts.is_empty() {
+            true => String::new(),
+            false => format!("<p class=\"card-details\">{}</p>", parts.join(" • ")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn structured_over_legacy() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        std::fs::write(dir.join(".title"), "Legacy title").unwrap();
+        std::fs::write(dir.join(".year"), "1999\n").unwrap();
+        std::fs::write(
+            dir.join(Info::FILENAME),
+            r#"{ "title": "Structured title", "genres": ["Drama"], "runtime": 142 }"#,
+        )
+        .unwrap();
+
+        let info = Info::load_dir(dir).unwrap();
+        assert_eq!(info.title.as_deref(), Some("Structured title"));
+        assert_eq!(info.year.as_deref(), Some("1999"));
+        assert_eq!(info.details.genres, vec!["Drama"]);
+        assert_eq!(
+            info.details.to_html_string(),
+            "<p class=\"card-details\">Drama • 2h22</p>"
+        );
+    }
+}
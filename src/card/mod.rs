@@ -1,32 +1,56 @@
 // Modules
 pub mod cards;
+mod index;
+pub mod info;
 mod movie;
+pub mod nfo;
 mod other;
+mod player;
 mod show;
+mod subtitle;
 
 // Imports
+use crate::html;
+use crate::probe::MediaInfo;
 use crate::utils::{
     get_extension, get_filename, get_filestem, get_rel_path_string, lazy_read_file_to_string,
 };
 use eyre::{eyre, OptionExt};
+use info::{Details, Info};
 use itertools::Itertools;
 use once_cell::sync::Lazy;
 use regex::Regex;
+use serde::{Deserialize, Serialize, Serializer};
 use std::fmt::Display;
 use std::path::{Path, PathBuf};
-use tracing::{info, instrument, trace, warn};
+use subtitle::{ApiSubtitle, Subtitle};
+use tracing::{instrument, trace, warn};
 
-static VIDEO_FILE_EXTENSIONS: [&str; 11] = [
+pub static VIDEO_FILE_EXTENSIONS: [&str; 11] = [
     "webm", "mkv", "vob", "ogg", "ogv", "avi", "move", "qt", "m4v", "m4v", "mp4",
 ];
 
-#[derive(Debug)]
+// parts of a movie split across discs, e.g. 'CD1', 'Disc 2', 'part.3'
+static DISC_DIR_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"^(?i)(?:cd|dvd|dis[ck]|part)[\s._-]*\d{1,2}$"#).unwrap());
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
 pub enum Card {
     Movie(movie::Movie),
     Show(show::Show),
     Other(other::Other),
 }
 
+/// Card as exposed by the JSON api, its relative paths are turned into urls
+#[derive(Debug, Serialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum ApiCard<'a> {
+    Movie(movie::ApiMovie<'a>),
+    Show(show::ApiShow<'a>),
+    Other(other::ApiOther<'a>),
+}
+
 impl Card {
     #[instrument(skip(base))]
     pub fn from_path(base: &Path, path: &Path) -> eyre::Result<Self> {
@@ -34,6 +58,23 @@ impl Card {
         let mut dot_fps: Vec<PathBuf> = Vec::new();
         let mut otr_fps: Vec<PathBuf> = Vec::new();
 
+        collect_fps(path, true, true, &mut vid_fps, &mut dot_fps, &mut otr_fps)?;
+
+        match vid_fps.len() {
+            // Other route
+            0 => other::Other::from_paths(base, path, vid_fps, dot_fps, otr_fps),
+            // Movie route
+            1 => movie::Movie::from_paths(base, path, vid_fps, dot_fps, otr_fps),
+            // Show route
+            2.. => show::Show::from_paths(base, path, vid_fps, dot_fps, otr_fps),
+        }
+    }
+
+    /// A directory is a card if it directly holds files, contains season or disc directories
+    /// or has no sub-directories at all, otherwise it's a category. The other sub-directories
+    /// of a card are part of it
+    pub fn is_card_dir(path: &Path) -> eyre::Result<bool> {
+        let mut has_sub_dirs = false;
         for fp in std::fs::read_dir(path)? {
             let fp = match fp {
                 Ok(fp) => fp.path(),
@@ -42,36 +83,33 @@ impl Card {
                     continue;
                 }
             };
-
-            if !fp.is_file() {
-                warn!("Found sub-directory, will be ignored");
+            if get_filename(&fp).starts_with('.') {
                 continue;
             }
-
-            if VIDEO_FILE_EXTENSIONS.contains(&get_extension(&fp)) {
-                vid_fps.push(fp);
-            } else if get_filename(&fp).starts_with('.') {
-                dot_fps.push(fp)
-            } else {
-                otr_fps.push(fp);
+            if !fp.is_dir()
+                || show::season_hint(&fp).is_some()
+                || DISC_DIR_RE.is_match(get_filename(&fp))
+            {
+                return Ok(true);
             }
+            has_sub_dirs = true;
         }
+        Ok(!has_sub_dirs)
+    }
 
-        match vid_fps.len() {
-            // Other route
-            0 => Err(eyre!("'Other' route not ready")),
-            // Movie route
-            1 => movie::Movie::from_paths(base, path, vid_fps, dot_fps, otr_fps),
-            // Show route
-            2.. => show::Show::from_paths(base, path, vid_fps, dot_fps, otr_fps),
+    pub fn to_api(&self) -> ApiCard<'_> {
+        match self {
+            Self::Movie(movie) => ApiCard::Movie(movie.to_api()),
+            Self::Show(show) => ApiCard::Show(show.to_api()),
+            Self::Other(other) => ApiCard::Other(other.to_api()),
         }
     }
 
-    pub fn into_html_string(self) -> String {
+    pub fn to_html_string(&self) -> String {
         match self {
-            Self::Movie(movie) => movie.into_html_string(),
-            Self::Show(show) => show.into_html_string(),
-            Self::Other(other) => String::from("OTHER NOT IMPLEMENTED"),
+            Self::Movie(movie) => movie.to_html_string(),
+            Self::Show(show) => show.to_html_string(),
+            Self::Other(other) => other.to_html_string(),
         }
     }
 
@@ -79,7 +117,69 @@ impl Card {
         match self {
             Self::Movie(movie) => &movie.title,
             Self::Show(show) => &show.title,
-            Self::Other(other) => "Other",
+            Self::Other(other) => &other.title,
+        }
+    }
+
+    pub fn get_id(&self) -> &str {
+        match self {
+            Self::Movie(movie) => &movie.id,
+            Self::Show(show) => &show.id,
+            Self::Other(other) => &other.id,
+        }
+    }
+
+    /// Player page of a movie, or of an episode of a show (the first one if none is given),
+    /// None if there is no such video
+    pub fn to_watch_html_string(&self, episode: Option<(u8, u8)>) -> Option<String> {
+        match (self, episode) {
+            (Self::Movie(movie), None) => Some(movie.to_watch_html_string()),
+            (Self::Show(show), episode) => show.to_watch_html_string(episode),
+            _ => None,
+        }
+    }
+
+    /// Whether the card has this video, the movie itself or an episode of a show
+    pub fn has_video(&self, episode: Option<(u8, u8)>) -> bool {
+        match (self, episode) {
+            (Self::Movie(_), None) => true,
+            (Self::Show(show), Some((season, episode))) => show
+                .episodes
+                .iter()
+                .any(|ep| ep.season == season && ep.episode == episode),
+            _ => false,
+        }
+    }
+
+    /// Whether the file at this relative path is a subtitle of the card
+    pub fn has_subtitle(&self, path: &str) -> bool {
+        let subtitles = match self {
+            Self::Movie(movie) => movie.subtitles.iter().collect_vec(),
+            Self::Show(show) => show
+                .episodes
+                .iter()
+                .flat_map(|ep| ep.subtitles.iter())
+                .collect_vec(),
+            Self::Other(_) => Vec::new(),
+        };
+        subtitles.iter().any(|subtitle| subtitle.filepath == path)
+    }
+
+    /// Whether the file at this relative path is a thumbnail of the card or one of the files
+    /// of an other card, videos and subtitles aside
+    pub fn has_file(&self, path: &str) -> bool {
+        match self {
+            Self::Movie(movie) => movie.has_file(path),
+            Self::Show(show) => show.has_file(path),
+            Self::Other(other) => other.has_file(path),
+        }
+    }
+
+    /// Seasons of the episodes of a show in ascending order, empty for other cards
+    pub fn seasons(&self) -> Vec<u8> {
+        match self {
+            Self::Show(show) => show.seasons.iter().map(|season| season.season).collect(),
+            _ => Vec::new(),
         }
     }
 }
@@ -93,11 +193,68 @@ trait CardMethods {
         otr_fps: Vec<PathBuf>,
     ) -> eyre::Result<Card>;
 
-    fn into_html_string(self) -> String;
+    fn to_html_string(&self) -> String;
+}
+
+// Recursively sorts the files of a card directory, dot-directories are skipped and
+// dot-files are only considered metadata at the root of the card, videos outside of the
+// root and season directories (e.g. 'Extras/trailer.mkv') are sorted with the other files
+fn collect_fps(
+    dir: &Path,
+    root: bool,
+    videos: bool,
+    vid_fps: &mut Vec<PathBuf>,
+    dot_fps: &mut Vec<PathBuf>,
+    otr_fps: &mut Vec<PathBuf>,
+) -> eyre::Result<()> {
+    for fp in std::fs::read_dir(dir)? {
+        let fp = match fp {
+            Ok(fp) => fp.path(),
+            Err(err) => {
+                warn!("{err}");
+                continue;
+            }
+        };
+
+        let is_hidden = get_filename(&fp).starts_with('.');
+
+        if fp.is_dir() {
+            if is_hidden {
+                trace!("Skipping hidden directory '{}'", fp.display());
+                continue;
+            }
+            let videos = videos && (!root || show::season_hint(&fp).is_some());
+            if let Err(err) = collect_fps(&fp, false, videos, vid_fps, dot_fps, otr_fps) {
+                warn!("{} - {err}", fp.display());
+            }
+        } else if videos && VIDEO_FILE_EXTENSIONS.contains(&get_extension(&fp)) {
+            vid_fps.push(fp);
+        } else if is_hidden {
+            if root {
+                dot_fps.push(fp)
+            }
+        } else {
+            otr_fps.push(fp);
+        }
+    }
+    Ok(())
+}
+
+/// Default title of a card, the name of its folder with '-' and '_' replaced by spaces
+pub fn title_from_folder_name(path: &Path) -> String {
+    let folder_name = get_filestem(path);
+    let folder_name = folder_name.replace("-", " ");
+    folder_name.replace("_", " ")
+}
+
+/// Stable identifier of a card, FNV-1a hash of its folder path relative to the target directory
+fn card_id(base: &Path, path: &Path) -> eyre::Result<String> {
+    let rel_path = get_rel_path_string(path, base).ok_or_eyre("Card path is crucial")?;
+    Ok(format!("{:016x}", crate::utils::fnv1a(rel_path.as_bytes())))
 }
 
 // Size of a file represented by MB
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct FileSize(u32);
 
 impl From<u64> for FileSize {
@@ -106,9 +263,107 @@ impl From<u64> for FileSize {
     }
 }
 
+// summary of the probed details of a video, e.g. '1h52 • 1080p • HEVC'
+fn media_html(media: Option<&MediaInfo>, pre: &str, post: &str) -> String {
+    let summary = media.map(MediaInfo::to_html_string).unwrap_or_default();
+    match summary.is_empty() {
+        true => String::new(),
+        false => format!("{pre}{summary}{post}"),
+    }
+}
+
+// serializes a relative path into an url served by the /res route
+fn serialize_res_url<S: Serializer>(path: &str, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&html::res_url(path))
+}
+
+fn serialize_thumbnail_url<S: Serializer>(
+    path: &Option<&str>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serialize_res_url(path.unwrap_or(".assets/default_thumbnail.png"), serializer)
+}
+
+// url of the thumbnail, escaped for use in an html attribute
+fn thumbnail_url(thumbnail: Option<&String>) -> String {
+    html::res_url(
+        thumbnail
+            .map(String::as_str)
+            .unwrap_or(".assets/default_thumbnail.png"),
+    )
+}
+
 fn display<T: Display>(input: Option<T>, pre: &str, post: &str, alt: &str) -> String {
     match input {
         Some(val) => format!("{pre}{}{post}", val),
         None => alt.to_owned(),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extras_are_not_episodes() {
+        let tmp = tempfile::tempdir().unwrap();
+        let base = tmp.path();
+        for fp in [
+            "Movie/Movie.mkv",
+            "Movie/Extras/Trailer.mkv",
+            "Show/Season 01/S01E01.mkv",
+            "Show/Season 01/Disc 2/S01E02.mkv",
+            "Show/Extras/Making of.mkv",
+        ] {
+            let fp = base.join(fp);
+            std::fs::create_dir_all(fp.parent().unwrap()).unwrap();
+            std::fs::write(fp, "").unwrap();
+        }
+
+        let card = Card::from_path(base, &base.join("Movie")).unwrap();
+        assert!(matches!(card, Card::Movie(_)));
+        let Card::Show(show) = Card::from_path(base, &base.join("Show")).unwrap() else {
+            panic!("not a show");
+        };
+        assert_eq!(show.episodes.len(), 2);
+    }
+
+    #[test]
+    fn card_dirs() {
+        let tmp = tempfile::tempdir().unwrap();
+        let base = tmp.path();
+        for fp in [
+            "Movies/Heat/Heat.mkv",
+            "Movies/Heat/Extras/Trailer.mkv",
+            "Movies/Zodiac/CD1/Zodiac.cd1.avi",
+            "Movies/Zodiac/CD2/Zodiac.cd2.avi",
+            "Manuals/index.pdf",
+            "Manuals/Camera/camera.pdf",
+            "Shows/Show/Season 01/S01E01.mkv",
+            "Shows/.hidden/notes",
+        ] {
+            let fp = base.join(fp);
+            std::fs::create_dir_all(fp.parent().unwrap()).unwrap();
+            std::fs::write(fp, "").unwrap();
+        }
+
+        let (mut dirs, mut categories) = cards::walk(base).unwrap();
+        dirs.sort();
+        categories.sort();
+        assert_eq!(
+            dirs,
+            ["Manuals", "Movies/Heat", "Movies/Zodiac", "Shows/Show"].map(|dir| base.join(dir))
+        );
+        assert_eq!(
+            categories,
+            ["", "Movies", "Shows"].map(|dir| base.join(dir))
+        );
+
+        // loose files and the files of the sub-directories make up an other card
+        let Card::Other(other) = Card::from_path(base, &base.join("Manuals")).unwrap() else {
+            panic!("not an other card");
+        };
+        assert!(other.has_file("Manuals/index.pdf"));
+        assert!(other.has_file("Manuals/Camera/camera.pdf"));
+    }
+}
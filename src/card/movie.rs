@@ -1,15 +1,74 @@
 use super::*;
 
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct Movie {
+    pub id: String,
     pub title: String,
     year: Option<String>,
     description: Option<String>,
-    // relative path, http-compatible
+    // relative path
     thumbnail: Option<String>,
-    // relative path, http-compatible
+    // relative path
     filepath: String,
     filesize: FileSize,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub subtitles: Vec<Subtitle>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    media: Option<MediaInfo>,
+    #[serde(flatten)]
+    details: Details,
+}
+
+/// Movie as exposed by the JSON api, the relative paths are turned into urls
+#[derive(Debug, Serialize)]
+pub struct ApiMovie<'a> {
+    id: &'a str,
+    title: &'a str,
+    year: Option<&'a str>,
+    description: Option<&'a str>,
+    #[serde(serialize_with = "serialize_thumbnail_url")]
+    thumbnail_url: Option<&'a str>,
+    #[serde(serialize_with = "serialize_res_url")]
+    file_url: &'a str,
+    size_mb: &'a FileSize,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    subtitles: Vec<ApiSubtitle<'a>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    media: Option<&'a MediaInfo>,
+    #[serde(flatten)]
+    details: &'a Details,
+}
+
+impl Movie {
+    pub(super) fn has_file(&self, path: &str) -> bool {
+        self.thumbnail.as_deref() == Some(path)
+    }
+
+    pub(super) fn to_api(&self) -> ApiMovie<'_> {
+        ApiMovie {
+            id: &self.id,
+            title: &self.title,
+            year: self.year.as_deref(),
+            description: self.description.as_deref(),
+            thumbnail_url: self.thumbnail.as_deref(),
+            file_url: &self.filepath,
+            size_mb: &self.filesize,
+            subtitles: self.subtitles.iter().map(Subtitle::to_api).collect(),
+            media: self.media.as_ref(),
+            details: &self.details,
+        }
+    }
+
+    pub(super) fn to_watch_html_string(&self) -> String {
+        player::page(
+            &self.id,
+            &self.title,
+            self.year.as_deref(),
+            &self.filepath,
+            &self.subtitles,
+            "",
+        )
+    }
 }
 
 impl CardMethods for Movie {
@@ -18,73 +77,63 @@ impl CardMethods for Movie {
         path: &Path,
         mut vid_fps: Vec<PathBuf>,
         dot_fps: Vec<PathBuf>,
-        _otr_fps: Vec<PathBuf>,
+        otr_fps: Vec<PathBuf>,
     ) -> eyre::Result<Card> {
-        let mut title = String::new();
-        let mut year = None;
-        let mut description = None;
-        let mut thumbnail = None;
-
-        for dot_fp in dot_fps.into_iter() {
-            match get_filestem(&dot_fp) {
-                ".title" => {
-                    if let Some(string) = lazy_read_file_to_string(&dot_fp) {
-                        title = string;
-                    };
-                }
-                ".year" => year = lazy_read_file_to_string(&dot_fp),
-                ".description" | ".descr" => description = lazy_read_file_to_string(&dot_fp),
-                ".thumbnail" => thumbnail = get_rel_path_string(&dot_fp, base),
-                _ => (),
-            }
-        }
-
-        if title.is_empty() {
-            let folder_name = get_filestem(path);
-            let folder_name = folder_name.replace("-", " ");
-            let folder_name = folder_name.replace("_", " ");
-            title = folder_name;
-        }
+        let info = Info::load(path, &dot_fps);
+        let thumbnail = info.thumbnail_rel_path(base, path);
 
         let fp = vid_fps.pop().unwrap();
         let filesize = FileSize::from(fp.metadata()?.len());
         let filepath = get_rel_path_string(&fp, base).ok_or_eyre("Video filepath is crucial")?;
+        let subtitles = Subtitle::find(base, &fp, &otr_fps);
+        let media = crate::probe::probe_cached(&fp);
 
         Ok(Card::Movie(Self {
-            title,
-            year,
-            description,
+            id: card_id(base, path)?,
+            title: info.title_or_folder_name(path),
+            year: info.year,
+            description: info.description,
             thumbnail,
             filepath,
             filesize,
+            subtitles,
+            media,
+            details: info.details,
         }))
     }
 
-    fn into_html_string(self) -> String {
+    fn to_html_string(&self) -> String {
         indoc::formatdoc! {
-            "<div class=\"card\">
+            "<div class=\"card\" data-id=\"{}\">
                 <div class=\"card-header\">
-                    <div class=\"card-header-thumbnail\"><img src=\"/res/{}\" /></div>
+                    <div class=\"card-header-thumbnail\"><img src=\"{}\" alt=\"{}\" /></div>
                     <div class=\"card-header-box\">
                         <div class=\"card-header-box-title\"><h2>{}</h2></div>
                         <div class=\"card-header-box-subtitle\">
                             <p>
-                                {} • {} MB • <a href=\"/res/{}\" download><img src=\"/res/.assets/download.svg\" /></a>
+                                {} • {} MB • {}{} <a href=\"{}\" download><img src=\"/res/.assets/download.svg\" /></a>
                             </p>
                         </div>
                     </div>
                 </div>
                 <div class=\"card-expand\">
+                    {}{}
                     <p>{}</p>
                 </div>
             </div>",
 
-            display(self.thumbnail, "", "", ".assets/default_thumbnail.png"),
-            self.title,
-            display(self.year, "", "", "????"),
+            self.id,
+            thumbnail_url(self.thumbnail.as_ref()),
+            html::attr(&self.title),
+            html::text(&self.title),
+            display(self.year.as_deref().map(html::text), "", "", "????"),
             self.filesize.0,
-            self.filepath,
-            display(self.description, "", "", "No description provided.")
+            Subtitle::to_html_string(&self.subtitles),
+            player::link(&self.id, None),
+            html::res_url(&self.filepath),
+            self.details.to_html_string(),
+            media_html(self.media.as_ref(), "<p class=\"card-media\">", "</p>"),
+            display(self.description.as_deref().map(html::text), "", "", "No description provided.")
         }
     }
 }
@@ -0,0 +1,348 @@
+use super::*;
+use info::{EpisodeInfo, Info};
+use quick_xml::events::Event;
+
+// conventional names of the poster next to an NFO file
+static POSTER_FILENAMES: [&str; 4] = ["poster.jpg", "poster.png", "folder.jpg", "folder.png"];
+
+/// Kodi/Jellyfin metadata file, only the fields Silvus knows about are kept
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Nfo {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    originaltitle: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    year: Option<String>,
+    // e.g. '1994-09-23', used when the year is missing
+    #[serde(skip_serializing_if = "Option::is_none")]
+    premiered: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    plot: Option<String>,
+    // in minutes, kept as a string as scrapers sometimes leave it empty
+    #[serde(skip_serializing_if = "Option::is_none")]
+    runtime: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    rating: Option<String>,
+    // newer files list a rating per source instead
+    #[serde(skip_serializing)]
+    ratings: Ratings,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    genre: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    tag: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    thumb: Vec<Thumb>,
+}
+
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+struct Thumb {
+    #[serde(rename = "@aspect", skip_serializing_if = "Option::is_none")]
+    aspect: Option<String>,
+    // url or path relative to the NFO file
+    #[serde(rename = "$text")]
+    path: String,
+}
+
+#[derive(Debug, Default, PartialEq, Deserialize)]
+#[serde(default)]
+struct Ratings {
+    rating: Vec<Rating>,
+}
+
+#[derive(Debug, Default, PartialEq, Deserialize)]
+#[serde(default)]
+struct Rating {
+    #[serde(rename = "@default")]
+    default: Option<String>,
+    value: Option<String>,
+}
+
+impl Nfo {
+    // names of the NFO files by root element
+    pub const MOVIE: (&'static str, &'static str) = ("movie", "movie.nfo");
+    pub const SHOW: (&'static str, &'static str) = ("tvshow", "tvshow.nfo");
+
+    /// Parses a 'movie' or 'tvshow' NFO, other root elements such as 'episodedetails' are refused
+    pub fn parse(xml: &str) -> eyre::Result<Self> {
+        let root = root_element(xml)?;
+        if !Self::is_card_root(&root) {
+            return Err(eyre!("Unexpected root element '{root}'"));
+        }
+        Ok(quick_xml::de::from_str(xml)?)
+    }
+
+    fn is_card_root(root: &str) -> bool {
+        root == Self::MOVIE.0 || root == Self::SHOW.0
+    }
+
+    /// Finds and reads the NFO file of a card, 'tvshow.nfo' and 'movie.nfo' take precedence
+    /// over NFO files named after the video
+    pub fn find(dir: &Path) -> Option<Self> {
+        let nfo_fps = std::fs::read_dir(dir)
+            .ok()?
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|fp| fp.is_file() && get_extension(fp).eq_ignore_ascii_case("nfo"))
+            .sorted_by_key(|fp| {
+                let filename = get_filename(fp);
+                filename != Self::SHOW.1 && filename != Self::MOVIE.1
+            })
+            .collect_vec();
+
+        nfo_fps.iter().find_map(|fp| {
+            let xml = lazy_read_file_to_string(fp)?;
+            // flat show folders also hold the NFO files of the episodes
+            if !root_element(&xml).is_ok_and(|root| Self::is_card_root(&root)) {
+                trace!("Skipping '{}', not a movie or show NFO", fp.display());
+                return None;
+            }
+            Self::parse(&xml)
+                .inspect_err(|err| warn!("Failed to parse '{}', '{err}'", fp.display()))
+                .ok()
+        })
+    }
+
+    /// Converts the NFO into card metadata, relative to the directory of the card
+    pub fn into_info(self, dir: &Path) -> Info {
+        // thumbnails are often urls, only local files can be served
+        let thumbnail = self
+            .thumb
+            .iter()
+            .sorted_by_key(|thumb| thumb.aspect.as_deref() != Some("poster"))
+            .map(|thumb| thumb.path.trim())
+            .chain(POSTER_FILENAMES)
+            .find(|path| !path.contains("://") && !path.is_empty() && dir.join(path).is_file())
+            .map(str::to_owned);
+
+        let rating = non_empty(self.rating).or_else(|| {
+            self.ratings
+                .rating
+                .into_iter()
+                .sorted_by_key(|rating| rating.default.as_deref() != Some("true"))
+                .find_map(|rating| non_empty(rating.value))
+        });
+
+        let year = non_empty(self.year).or_else(|| {
+            non_empty(self.premiered).and_then(|date| date.get(..4).map(str::to_owned))
+        });
+
+        Info {
+            title: non_empty(self.title),
+            subtitle: None,
+            year,
+            description: non_empty(self.plot),
+            thumbnail,
+            details: info::Details {
+                original_title: non_empty(self.originaltitle),
+                genres: self.genre,
+                runtime: non_empty(self.runtime).and_then(|runtime| runtime.parse().ok()),
+                rating: rating.and_then(|rating| rating.parse().ok()),
+                tags: self.tag,
+            },
+            episodes: Vec::new(),
+        }
+    }
+
+    pub fn from_info(info: &Info) -> Self {
+        Self {
+            title: info.title.clone(),
+            originaltitle: info.details.original_title.clone(),
+            year: info.year.clone(),
+            premiered: None,
+            plot: info.description.clone(),
+            runtime: info.details.runtime.map(|runtime| runtime.to_string()),
+            rating: info.details.rating.map(|rating| rating.to_string()),
+            ratings: Ratings::default(),
+            genre: info.details.genres.clone(),
+            tag: info.details.tags.clone(),
+            thumb: info
+                .thumbnail
+                .iter()
+                .map(|path| Thumb {
+                    aspect: Some("poster".to_owned()),
+                    path: path.clone(),
+                })
+                .collect(),
+        }
+    }
+
+    /// Serializes the NFO with the given root element, 'movie' or 'tvshow'
+    pub fn to_xml(&self, root: &str) -> eyre::Result<String> {
+        let mut xml =
+            String::from("<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\" ?>\n");
+        let mut serializer = quick_xml::se::Serializer::with_root(&mut xml, Some(root))?;
+        serializer.indent(' ', 2);
+        self.serialize(serializer)?;
+        xml.push('\n');
+        Ok(xml)
+    }
+}
+
+/// Kodi/Jellyfin metadata file of an episode, named after its video
+#[derive(Debug, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct EpisodeNfo {
+    title: Option<String>,
+    plot: Option<String>,
+    // e.g. '1990-04-08'
+    aired: Option<String>,
+}
+
+impl EpisodeNfo {
+    const ROOT: &'static str = "episodedetails";
+
+    pub fn parse(xml: &str) -> eyre::Result<Self> {
+        let root = root_element(xml)?;
+        if root != Self::ROOT {
+            return Err(eyre!("Unexpected root element '{root}'"));
+        }
+        Ok(quick_xml::de::from_str(xml)?)
+    }
+
+    /// Reads the NFO file next to a video, e.g. 'S01E01.nfo' for 'S01E01.mkv'
+    pub fn find(video_fp: &Path) -> Option<Self> {
+        let fp = video_fp.with_extension("nfo");
+        if !fp.is_file() {
+            return None;
+        }
+        let xml = lazy_read_file_to_string(&fp)?;
+        Self::parse(&xml)
+            .inspect_err(|err| warn!("Failed to parse '{}', '{err}'", fp.display()))
+            .ok()
+    }
+
+    pub fn into_info(self, season: u8, episode: u8) -> EpisodeInfo {
+        EpisodeInfo {
+            season,
+            episode,
+            title: non_empty(self.title),
+            description: non_empty(self.plot),
+            air_date: non_empty(self.aired),
+        }
+    }
+}
+
+fn non_empty(string: Option<String>) -> Option<String> {
+    string
+        .map(|string| string.trim().to_owned())
+        .filter(|string| !string.is_empty())
+}
+
+// Name of the root element, quick-xml ignores it when deserializing
+fn root_element(xml: &str) -> eyre::Result<String> {
+    let mut reader = quick_xml::Reader::from_str(xml);
+    loop {
+        match reader.read_event()? {
+            Event::Start(element) | Event::Empty(element) => {
+                return Ok(String::from_utf8_lossy(element.local_name().as_ref()).into_owned())
+            }
+            Event::Eof => return Err(eyre!("No root element")),
+            _ => (),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn kodi_movie() {
+        let nfo = Nfo::parse(
+            r#"<?xml version="1.0" encoding="UTF-8" standalone="yes" ?>
+            <movie>
+                <title>Brazil</title>
+                <originaltitle>Brazil</originaltitle>
+                <ratings><rating name="imdb" max="10"><value>7.9</value></rating></ratings>
+                <plot>A bureaucrat in a dystopic society becomes an enemy of the state.</plot>
+                <runtime>142</runtime>
+                <thumb aspect="landscape">https://example.com/fanart.jpg</thumb>
+                <thumb aspect="poster">https://example.com/poster.jpg</thumb>
+                <genre>Drama</genre>
+                <genre>Sci-Fi</genre>
+                <premiered>1985-02-20</premiered>
+                <actor><name>Jonathan Pryce</name></actor>
+            </movie>"#,
+        )
+        .unwrap();
+        let info = nfo.into_info(Path::new("/nonexistent"));
+        assert_eq!(info.title.as_deref(), Some("Brazil"));
+        assert_eq!(info.year.as_deref(), Some("1985"));
+        assert_eq!(info.details.genres, vec!["Drama", "Sci-Fi"]);
+        assert_eq!(info.details.runtime, Some(142));
+        assert_eq!(info.details.rating, Some(7.9));
+        assert_eq!(info.thumbnail, None);
+    }
+
+    #[test]
+    fn round_trip() {
+        let info = Info {
+            title: Some("Twin Peaks".to_string()),
+            year: Some("1990".to_string()),
+            description: Some("Who killed Laura Palmer? <spoilers> & more".to_string()),
+            thumbnail: Some(".thumbnail.jpg".to_string()),
+            details: info::Details {
+                genres: vec!["Crime".to_string(), "Drama".to_string()],
+                rating: Some(8.8),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let xml = Nfo::from_info(&info).to_xml(Nfo::SHOW.0).unwrap();
+        assert!(xml.contains("<tvshow>"));
+        assert!(xml.contains(r#"<thumb aspect="poster">.thumbnail.jpg</thumb>"#));
+
+        let parsed = Nfo::parse(&xml).unwrap();
+        assert_eq!(parsed, Nfo::from_info(&info));
+    }
+
+    #[test]
+    fn episode_nfo_ignored() {
+        let tmp = tempfile::tempdir().unwrap();
+        std::fs::write(
+            tmp.path().join("S01E01.nfo"),
+            "<episodedetails><title>Pilot</title><plot>It begins.</plot></episodedetails>",
+        )
+        .unwrap();
+        assert!(Nfo::parse("<episodedetails><title>Pilot</title></episodedetails>").is_err());
+        assert_eq!(Nfo::find(tmp.path()), None);
+
+        std::fs::write(
+            tmp.path().join("Show.nfo"),
+            "<?xml version=\"1.0\"?>\n<!-- scraped -->\n<tvshow><title>Show</title></tvshow>",
+        )
+        .unwrap();
+        let nfo = Nfo::find(tmp.path()).unwrap();
+        assert_eq!(nfo.title.as_deref(), Some("Show"));
+    }
+
+    #[test]
+    fn episode_nfo() {
+        let tmp = tempfile::tempdir().unwrap();
+        let video_fp = tmp.path().join("Show.S01E02.mkv");
+        assert_eq!(EpisodeNfo::find(&video_fp), None);
+
+        std::fs::write(
+            tmp.path().join("Show.S01E02.nfo"),
+            r#"<episodedetails>
+                <title>Traces to Nowhere</title>
+                <season>1</season>
+                <episode>2</episode>
+                <plot> Cooper questions Laura's friends. </plot>
+                <aired>1990-04-12</aired>
+            </episodedetails>"#,
+        )
+        .unwrap();
+        let info = EpisodeNfo::find(&video_fp).unwrap().into_info(1, 2);
+        assert_eq!(info.title.as_deref(), Some("Traces to Nowhere"));
+        assert_eq!(
+            info.description.as_deref(),
+            Some("Cooper questions Laura's friends.")
+        );
+        assert_eq!(info.air_date.as_deref(), Some("1990-04-12"));
+        assert!(EpisodeNfo::parse("<tvshow><title>Twin Peaks</title></tvshow>").is_err());
+    }
+}
@@ -1,9 +1,146 @@
 use super::*;
 
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct Other {
+    pub id: String,
     pub title: String,
     description: Option<String>,
+    // relative path
     thumbnail: Option<String>,
-    content: Vec<(String, FileSize)>,
+    content: Vec<File>,
+    #[serde(flatten)]
+    details: Details,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct File {
+    // relative path
+    filepath: String,
+    filesize: FileSize,
+}
+
+/// Other card as exposed by the JSON api, the relative paths are turned into urls
+#[derive(Debug, Serialize)]
+pub struct ApiOther<'a> {
+    id: &'a str,
+    title: &'a str,
+    description: Option<&'a str>,
+    #[serde(serialize_with = "serialize_thumbnail_url")]
+    thumbnail_url: Option<&'a str>,
+    content: Vec<ApiFile<'a>>,
+    #[serde(flatten)]
+    details: &'a Details,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ApiFile<'a> {
+    #[serde(serialize_with = "serialize_res_url")]
+    file_url: &'a str,
+    size_mb: &'a FileSize,
+}
+
+impl Other {
+    pub(super) fn has_file(&self, path: &str) -> bool {
+        self.thumbnail.as_deref() == Some(path)
+            || self.content.iter().any(|file| file.filepath == path)
+    }
+
+    pub(super) fn to_api(&self) -> ApiOther<'_> {
+        ApiOther {
+            id: &self.id,
+            title: &self.title,
+            description: self.description.as_deref(),
+            thumbnail_url: self.thumbnail.as_deref(),
+            content: self
+                .content
+                .iter()
+                .map(|file| ApiFile {
+                    file_url: &file.filepath,
+                    size_mb: &file.filesize,
+                })
+                .collect(),
+            details: &self.details,
+        }
+    }
+}
+
+impl CardMethods for Other {
+    fn from_paths(
+        base: &Path,
+        path: &Path,
+        _vid_fps: Vec<PathBuf>,
+        dot_fps: Vec<PathBuf>,
+        otr_fps: Vec<PathBuf>,
+    ) -> eyre::Result<Card> {
+        let info = Info::load(path, &dot_fps);
+        let thumbnail = info.thumbnail_rel_path(base, path);
+
+        let content = otr_fps
+            .into_iter()
+            .filter_map(|fp| {
+                let filesize = FileSize::from(fp.metadata().ok()?.len());
+                let rel_fp = get_rel_path_string(&fp, base)?;
+                Some(File {
+                    filepath: rel_fp,
+                    filesize,
+                })
+            })
+            .sorted_by(|a, b| a.filepath.cmp(&b.filepath))
+            .collect_vec();
+
+        if content.is_empty() {
+            return Err(eyre!("No files found"));
+        }
+
+        Ok(Card::Other(Self {
+            id: card_id(base, path)?,
+            title: info.title_or_folder_name(path),
+            description: info.description,
+            thumbnail,
+            content,
+            details: info.details,
+        }))
+    }
+
+    fn to_html_string(&self) -> String {
+        indoc::formatdoc! {
+            "<div class=\"card\" data-id=\"{}\">
+                <div class=\"card-header\">
+                    <div class=\"card-header-thumbnail\"><img src=\"{}\" alt=\"{}\" /></div>
+                    <div class=\"card-header-box\">
+                        <div class=\"card-header-box-title\"><h2>{}</h2></div>
+                        <div class=\"card-header-box-subtitle\">
+                            <p>{} {}</p>
+                        </div>
+                    </div>
+                </div>
+                <div class=\"card-expand\">
+                    {}
+                    <p>
+                        {}
+                    </p>
+                    <ul>
+                        {}
+                    </ul>
+                </div>
+            </div>",
+
+            self.id,
+            thumbnail_url(self.thumbnail.as_ref()),
+            html::attr(&self.title),
+            html::text(&self.title),
+            self.content.len(),
+            if self.content.len() == 1 { "file" } else { "files" },
+            self.details.to_html_string(),
+            display(self.description.as_deref().map(html::text), "", "", "No description provided."),
+            self.content.iter().map(|file| {
+                format!(
+                    "<li>{} • {}  MB • <a href=\"{}\" download><img src=\"/res/.assets/download.svg\" /></a></li>",
+                    html::text(file.filepath.rsplit('/').next().unwrap_or(&file.filepath)),
+                    file.filesize.0,
+                    html::res_url(&file.filepath),
+                )
+            }).fold(String::new(), |acc, x| acc + &x + "\n")
+        }
+    }
 }
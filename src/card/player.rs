@@ -0,0 +1,69 @@
+use super::*;
+use cards::STYLE;
+
+static SCRIPT: &str = include_str!("../../assets/player.js");
+
+/// Player page of a single video, streamed from the /res route, the key identifies the video
+/// in the watch progress store
+pub(super) fn page(
+    key: &str,
+    title: &str,
+    subtitle: Option<&str>,
+    filepath: &str,
+    subtitles: &[Subtitle],
+    nav: &str,
+) -> String {
+    indoc::formatdoc! {
+        "<!doctype html>
+        <html lang=\"en\">
+            <head>
+                <meta charset=\"UTF-8\" />
+                <meta name=\"viewport\" content=\"width=device-width, initial-scale=1.0\" />
+                <meta name=\"description\" content=\"server\" />
+                <meta name=\"author\" content=\"anesthetice\" />
+                <title>{} - Silvus</title>
+                <style>
+                    {}
+                </style>
+            </head>
+            <body>
+                <div class=\"player\">
+                    <div class=\"player-header\">
+                        <a href=\"/\">&larr; library</a>
+                        <h2>{}</h2>
+                        {}
+                    </div>
+                    <video controls autoplay preload=\"metadata\" data-key=\"{}\" src=\"{}\">
+                        {}
+                    </video>
+                    {}
+                </div>
+                <script>
+                    {}
+                </script>
+            </body>
+        </html>
+        ",
+        html::text(title),
+        STYLE,
+        html::text(title),
+        display(subtitle.map(html::text), "<p>", "</p>", ""),
+        html::attr(key),
+        html::res_url(filepath),
+        subtitles
+            .iter()
+            .map(Subtitle::to_track_html_string)
+            .join("\n"),
+        nav,
+        SCRIPT,
+    }
+}
+
+/// Link to the player page of a card, or of one of its episodes
+pub(super) fn link(id: &str, episode: Option<(u8, u8)>) -> String {
+    let href = match episode {
+        Some((season, episode)) => format!("/watch/{id}/{season}/{episode}"),
+        None => format!("/watch/{id}"),
+    };
+    format!("<a href=\"{href}\"><img src=\"/res/.assets/play.svg\" /></a>")
+}
@@ -1,18 +1,297 @@
 use super::*;
+use nfo::EpisodeNfo;
 
 static SRE: Lazy<Regex> = Lazy::new(|| Regex::new(r#"[Ss]\d{1,2}"#).unwrap());
 static ERE: Lazy<Regex> = Lazy::new(|| Regex::new(r#"[Ee]\d{1,2}"#).unwrap());
+// e.g. 'Season 01', 'season_2', 'Season.10'
+static SEASON_DIR_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"^(?i)season[\s._-]*(\d{1,2})$"#).unwrap());
+// fallback episode number for files in season directories, e.g. '03 - Pilot' or 'Show_03'
+static NUM_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"(?:^|[^0-9])(\d{1,2})(?:[^0-9]|$)"#).unwrap());
 
-#[derive(Debug)]
+/// Returns the season number if the directory name looks like 'Season N'
+pub fn season_hint(dir: &Path) -> Option<u8> {
+    SEASON_DIR_RE
+        .captures(get_filename(dir))?
+        .get(1)?
+        .as_str()
+        .parse::<u8>()
+        .ok()
+}
+
+#[derive(Debug, Serialize, Deserialize)]
 pub struct Show {
+    pub id: String,
     pub title: String,
     subtitle: Option<String>,
     year: Option<String>,
     description: Option<String>,
     // relative filepath
     thumbnail: Option<String>,
-    // season, episode, relative filepath, size
-    episodes: Vec<(u8, u8, String, FileSize)>,
+    pub episodes: Vec<Episode>,
+    pub seasons: Vec<Season>,
+    #[serde(flatten)]
+    details: Details,
+}
+
+// Group of episodes, the metadata comes from the dot-files of its 'Season N' directory
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Season {
+    pub season: u8,
+    title: Option<String>,
+    year: Option<String>,
+    description: Option<String>,
+    // relative filepath
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    thumbnail: Option<String>,
+    // total of its episodes
+    filesize: FileSize,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Episode {
+    pub season: u8,
+    pub episode: u8,
+    // relative filepath
+    filepath: String,
+    filesize: FileSize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    title: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    air_date: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub subtitles: Vec<Subtitle>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    media: Option<MediaInfo>,
+}
+
+/// Show as exposed by the JSON api, the relative paths are turned into urls
+#[derive(Debug, Serialize)]
+pub struct ApiShow<'a> {
+    id: &'a str,
+    title: &'a str,
+    subtitle: Option<&'a str>,
+    year: Option<&'a str>,
+    description: Option<&'a str>,
+    #[serde(serialize_with = "serialize_thumbnail_url")]
+    thumbnail_url: Option<&'a str>,
+    episodes: Vec<ApiEpisode<'a>>,
+    seasons: Vec<ApiSeason<'a>>,
+    #[serde(flatten)]
+    details: &'a Details,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ApiSeason<'a> {
+    season: u8,
+    title: Option<&'a str>,
+    year: Option<&'a str>,
+    description: Option<&'a str>,
+    #[serde(
+        serialize_with = "serialize_thumbnail_url",
+        skip_serializing_if = "Option::is_none"
+    )]
+    thumbnail_url: Option<&'a str>,
+    size_mb: &'a FileSize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ApiEpisode<'a> {
+    season: u8,
+    episode: u8,
+    #[serde(serialize_with = "serialize_res_url")]
+    file_url: &'a str,
+    size_mb: &'a FileSize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    title: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    description: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    air_date: Option<&'a str>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    subtitles: Vec<ApiSubtitle<'a>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    media: Option<&'a MediaInfo>,
+}
+
+impl Episode {
+    // e.g. 'S01E03 - Pilot'
+    fn label(&self) -> String {
+        let code = format!("S{:0>2}E{:0>2}", self.season, self.episode);
+        match &self.title {
+            Some(title) => format!("{code} - {title}"),
+            None => code,
+        }
+    }
+
+    // air date and description below the episode line, empty if neither is known
+    fn details_html(&self) -> String {
+        let details = [
+            self.air_date.as_deref().map(html::text),
+            self.description.as_deref().map(html::text),
+        ]
+        .into_iter()
+        .flatten()
+        .join(" — ");
+        match details.is_empty() {
+            true => String::new(),
+            false => format!("<p class=\"episode-details\">{details}</p>"),
+        }
+    }
+}
+
+impl Show {
+    pub(super) fn has_file(&self, path: &str) -> bool {
+        self.thumbnail.as_deref() == Some(path)
+            || self
+                .seasons
+                .iter()
+                .any(|season| season.thumbnail.as_deref() == Some(path))
+    }
+
+    pub(super) fn to_api(&self) -> ApiShow<'_> {
+        ApiShow {
+            id: &self.id,
+            title: &self.title,
+            subtitle: self.subtitle.as_deref(),
+            year: self.year.as_deref(),
+            description: self.description.as_deref(),
+            thumbnail_url: self.thumbnail.as_deref(),
+            episodes: self.episodes_api(),
+            seasons: self
+                .seasons
+                .iter()
+                .map(|season| ApiSeason {
+                    season: season.season,
+                    title: season.title.as_deref(),
+                    year: season.year.as_deref(),
+                    description: season.description.as_deref(),
+                    thumbnail_url: season.thumbnail.as_deref(),
+                    size_mb: &season.filesize,
+                })
+                .collect(),
+            details: &self.details,
+        }
+    }
+
+    /// Episodes as exposed by the JSON api
+    pub fn episodes_api(&self) -> Vec<ApiEpisode<'_>> {
+        self.episodes
+            .iter()
+            .map(|ep| ApiEpisode {
+                season: ep.season,
+                episode: ep.episode,
+                file_url: &ep.filepath,
+                size_mb: &ep.filesize,
+                title: ep.title.as_deref(),
+                description: ep.description.as_deref(),
+                air_date: ep.air_date.as_deref(),
+                subtitles: ep.subtitles.iter().map(Subtitle::to_api).collect(),
+                media: ep.media.as_ref(),
+            })
+            .collect()
+    }
+
+    // collapsible section of a season with its episodes, open if it's the only one
+    fn season_html(&self, season: &Season) -> String {
+        let header = [
+            Some(format!("season  {:0>2}", season.season)),
+            season.title.as_deref().map(html::text),
+            season.year.as_deref().map(html::text),
+            Some(format!("{}  MB", season.filesize.0)),
+        ]
+        .into_iter()
+        .flatten()
+        .join(" • ");
+
+        let episodes = self
+            .episodes
+            .iter()
+            .filter(|ep| ep.season == season.season)
+            .map(|ep| {
+                format!(
+                    "<li data-key=\"{}/{}/{}\">episode  {:0>2} • {}{}{}  MB • {}{} <a href=\"{}\" download><img src=\"/res/.assets/download.svg\" /></a>{}</li>",
+                    self.id,
+                    ep.season,
+                    ep.episode,
+                    ep.episode,
+                    display(ep.title.as_deref().map(html::text), "<span class=\"episode-title\">", "</span> • ", ""),
+                    media_html(ep.media.as_ref(), "", " • "),
+                    ep.filesize.0,
+                    Subtitle::to_html_string(&ep.subtitles),
+                    player::link(&self.id, Some((ep.season, ep.episode))),
+                    html::res_url(&ep.filepath),
+                    ep.details_html(),
+                )
+            })
+            .join("\n");
+
+        indoc::formatdoc! {
+            "<details class=\"season\"{}>
+                <summary>{}</summary>
+                {}{}
+                <ul>
+                    {}
+                </ul>
+            </details>",
+
+            if self.seasons.len() == 1 { " open" } else { "" },
+            header,
+            display(
+                season.thumbnail.as_deref().map(html::res_url),
+                "<img class=\"season-thumbnail\" src=\"",
+                "\" />",
+                "",
+            ),
+            display(season.description.as_deref().map(html::text), "<p>", "</p>", ""),
+            episodes,
+        }
+    }
+
+    // player of an episode with links to the previous and next ones
+    pub(super) fn to_watch_html_string(&self, episode: Option<(u8, u8)>) -> Option<String> {
+        let idx = match episode {
+            Some((season, episode)) => self
+                .episodes
+                .iter()
+                .position(|ep| ep.season == season && ep.episode == episode)?,
+            None => 0,
+        };
+        let ep = self.episodes.get(idx)?;
+
+        let nav_link = |ep: &Episode, class: &str, arrow: &str| {
+            format!(
+                "<a class=\"{class}\" href=\"/watch/{}/{}/{}\">{arrow} {}</a>",
+                self.id,
+                ep.season,
+                ep.episode,
+                html::text(&ep.label()),
+            )
+        };
+        let nav = [
+            idx.checked_sub(1)
+                .and_then(|idx| self.episodes.get(idx))
+                .map(|ep| nav_link(ep, "player-prev", "&larr;")),
+            self.episodes
+                .get(idx + 1)
+                .map(|ep| nav_link(ep, "player-next", "&rarr;")),
+        ]
+        .into_iter()
+        .flatten()
+        .join("\n");
+
+        Some(player::page(
+            &format!("{}/{}/{}", self.id, ep.season, ep.episode),
+            &self.title,
+            Some(&ep.label()),
+            &ep.filepath,
+            &ep.subtitles,
+            &format!("<div class=\"player-nav\">{nav}</div>"),
+        ))
+    }
 }
 
 impl CardMethods for Show {
@@ -21,35 +300,10 @@ impl CardMethods for Show {
         path: &Path,
         vid_fps: Vec<PathBuf>,
         dot_fps: Vec<PathBuf>,
-        _otr_fps: Vec<PathBuf>,
+        otr_fps: Vec<PathBuf>,
     ) -> eyre::Result<Card> {
-        let mut title = String::new();
-        let mut subtitle = None;
-        let mut year = None;
-        let mut description = None;
-        let mut thumbnail = None;
-
-        for dot_fp in dot_fps.into_iter() {
-            match get_filestem(&dot_fp) {
-                ".title" => {
-                    if let Some(string) = lazy_read_file_to_string(&dot_fp) {
-                        title = string;
-                    };
-                }
-                ".subtitle" | ".subt" => subtitle = lazy_read_file_to_string(&dot_fp),
-                ".year" => year = lazy_read_file_to_string(&dot_fp),
-                ".description" | ".descr" => description = lazy_read_file_to_string(&dot_fp),
-                ".thumbnail" => thumbnail = get_rel_path_string(&dot_fp, base),
-                _ => (),
-            }
-        }
-
-        if title.is_empty() {
-            let folder_name = get_filestem(path);
-            let folder_name = folder_name.replace("-", " ");
-            let folder_name = folder_name.replace("_", " ");
-            title = folder_name;
-        }
+        let info = Info::load(path, &dot_fps);
+        let thumbnail = info.thumbnail_rel_path(base, path);
 
         let episodes = vid_fps
             .into_iter()
@@ -59,48 +313,128 @@ impl CardMethods for Show {
 
                 let fs = get_filestem(&fp);
 
-                let season = match SRE.find(fs) {
-                    Some(season) => {
+                // closest 'Season N' directory between the file and the card directory
+                let hint = fp
+                    .ancestors()
+                    .skip(1)
+                    .take_while(|dir| *dir != path)
+                    .find_map(season_hint);
+
+                let season = match (SRE.find(fs), hint) {
+                    (Some(season), _) => {
                         // unwrapping because regex rule assures this is valid
                         season.as_str()[1..].parse::<u8>().unwrap()
                     }
-                    None => {
+                    (None, Some(season)) => season,
+                    (None, None) => {
                         warn!("Failed to match season in filename, assuming season 01");
                         1_u8
                     }
                 };
 
-                let Some(episode) = ERE.find(fs) else {
-                    trace!("Failed to match episode in filename");
-                    return None;
+                let episode = match (ERE.find(fs), hint) {
+                    (Some(episode), _) => {
+                        // unwrapping because regex rule assures this is valid
+                        episode.as_str()[1..].parse::<u8>().unwrap()
+                    }
+                    (None, Some(_)) => {
+                        let Some(episode) = NUM_RE.captures(fs) else {
+                            trace!("Failed to match episode in filename");
+                            return None;
+                        };
+                        // unwrapping because regex rule assures this is valid
+                        episode[1].parse::<u8>().unwrap()
+                    }
+                    (None, None) => {
+                        trace!("Failed to match episode in filename");
+                        return None;
+                    }
                 };
-                // unwrapping because regex rule assures this is valid
-                let episode = episode.as_str()[1..].parse::<u8>().unwrap();
 
-                Some((season, episode, rel_fp, filesize))
+                // '.silvus.json' first, then the NFO file next to the video
+                let mut episode_info = info.episode(season, episode).cloned().unwrap_or_default();
+                let incomplete = episode_info.title.is_none()
+                    || episode_info.description.is_none()
+                    || episode_info.air_date.is_none();
+                if let Some(nfo) = incomplete.then(|| EpisodeNfo::find(&fp)).flatten() {
+                    let nfo = nfo.into_info(season, episode);
+                    episode_info.title = episode_info.title.or(nfo.title);
+                    episode_info.description = episode_info.description.or(nfo.description);
+                    episode_info.air_date = episode_info.air_date.or(nfo.air_date);
+                }
+
+                Some(Episode {
+                    season,
+                    episode,
+                    filepath: rel_fp,
+                    filesize,
+                    title: episode_info.title,
+                    description: episode_info.description,
+                    air_date: episode_info.air_date,
+                    subtitles: Subtitle::find(base, &fp, &otr_fps),
+                    media: crate::probe::probe_cached(&fp),
+                })
             })
-            .sorted_by_key(|(s, e, ..)| {
-                let s = u16::from(*s);
-                let e = u16::from(*e);
+            .sorted_by_key(|ep| {
+                let s = u16::from(ep.season);
+                let e = u16::from(ep.episode);
                 s * 100 + e
             })
             .collect_vec();
 
+        // 'Season N' directories directly inside the card, if any
+        let season_dirs = std::fs::read_dir(path)?
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|dir| dir.is_dir())
+            .filter_map(|dir| Some((season_hint(&dir)?, dir)))
+            .collect_vec();
+
+        let seasons = episodes
+            .iter()
+            .chunk_by(|ep| ep.season)
+            .into_iter()
+            .map(|(season, eps)| {
+                let filesize = eps.fold(0_u32, |acc, ep| acc.saturating_add(ep.filesize.0));
+                let info = season_dirs
+                    .iter()
+                    .find(|(hint, _)| *hint == season)
+                    .and_then(|(_, dir)| {
+                        Info::load_dir(dir)
+                            .inspect_err(|err| warn!("{} - {err}", dir.display()))
+                            .ok()
+                            .map(|info| (info.thumbnail_rel_path(base, dir), info))
+                    });
+                let (thumbnail, info) = info.unwrap_or_default();
+                Season {
+                    season,
+                    title: info.title,
+                    year: info.year,
+                    description: info.description,
+                    thumbnail,
+                    filesize: FileSize(filesize),
+                }
+            })
+            .collect_vec();
+
         Ok(Card::Show(Self {
-            title,
-            subtitle,
-            year,
-            description,
+            id: card_id(base, path)?,
+            title: info.title_or_folder_name(path),
+            subtitle: info.subtitle,
+            year: info.year,
+            description: info.description,
             thumbnail,
             episodes,
+            seasons,
+            details: info.details,
         }))
     }
 
-    fn into_html_string(self) -> String {
+    fn to_html_string(&self) -> String {
         indoc::formatdoc! {
-            "<div class=\"card\">
+            "<div class=\"card\" data-id=\"{}\">
                 <div class=\"card-header\">
-                    <div class=\"card-header-thumbnail\"><img src=\"/res/{}\" /></div>
+                    <div class=\"card-header-thumbnail\"><img src=\"{}\" alt=\"{}\" /></div>
                     <div class=\"card-header-box\">
                         <div class=\"card-header-box-title\"><h2>{}</h2></div>
                         <div class=\"card-header-box-subtitle\">
@@ -109,29 +443,72 @@ impl CardMethods for Show {
                     </div>
                 </div>
                 <div class=\"card-expand\">
+                    {}
                     <p>
                         {}
                     </p>
-                    <ul>
-                        {}
-                    </ul>
+                    {}
                 </div>
             </div>",
 
-            display(self.thumbnail, "", "", ".assets/default_thumbnail.png"),
-            self.title,
-            display(self.year, "", "", "????"),
-            display(self.subtitle, "• ", "", ""),
-            display(self.description, "", "", "No description provided."),
-            self.episodes.into_iter().map(|(s, e, fp, size)| {
-                format!(
-                    "<li>season  {:0>2} • episode  {:0>2} • {}  MB • <a href=\"/res/{}\" download><img src=\"/res/.assets/download.svg\" /></a></li>",
-                    s,
-                    e,
-                    size.0,
-                    fp,
-                )
-            }).fold(String::new(), |acc, x| acc + &x + "\n")
+            self.id,
+            thumbnail_url(self.thumbnail.as_ref()),
+            html::attr(&self.title),
+            html::text(&self.title),
+            display(self.year.as_deref().map(html::text), "", "", "????"),
+            display(self.subtitle.as_deref().map(html::text), "• ", "", ""),
+            self.details.to_html_string(),
+            display(self.description.as_deref().map(html::text), "", "", "No description provided."),
+            self.seasons.iter().map(|season| self.season_html(season)).join("\n")
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn season_groups() {
+        let tmp = tempfile::tempdir().unwrap();
+        let base = tmp.path();
+        let dir = base.join("Show");
+        for (fp, content) in [
+            ("Season 01/01 - Pilot.mkv", ""),
+            ("Season 01/02.mkv", ""),
+            ("Season 01/Show_03.mkv", ""),
+            ("Season 02/S02E01.mkv", ""),
+            ("Season 02/.title", "The Return"),
+            ("Season 02/.description", "Back in town"),
+        ] {
+            let fp = dir.join(fp);
+            std::fs::create_dir_all(fp.parent().unwrap()).unwrap();
+            std::fs::write(fp, content).unwrap();
+        }
+
+        let card = Card::from_path(base, &dir).unwrap();
+        assert_eq!(card.seasons(), vec![1, 2]);
+        let Card::Show(show) = card else {
+            panic!("not a show");
+        };
+        let episodes = |season: u8| {
+            show.episodes
+                .iter()
+                .filter(|ep| ep.season == season)
+                .map(|ep| ep.episode)
+                .collect_vec()
+        };
+        assert_eq!(episodes(1), vec![1, 2, 3]);
+        assert_eq!(show.seasons[0].title, None);
+        assert_eq!(show.seasons[1].title.as_deref(), Some("The Return"));
+        assert_eq!(show.seasons[1].description.as_deref(), Some("Back in town"));
+        let html = show.to_html_string();
+        assert_eq!(html.matches("<details class=\"season\">").count(), 2);
+
+        let page = show.to_watch_html_string(Some((1, 3))).unwrap();
+        assert!(page.contains("Season%2001/Show_03.mkv"));
+        assert!(page.contains(&format!("/watch/{}/1/2", show.id)));
+        assert!(page.contains(&format!("/watch/{}/2/1", show.id)));
+        assert!(show.to_watch_html_string(Some((3, 1))).is_none());
+    }
+}
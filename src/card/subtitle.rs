@@ -0,0 +1,148 @@
+use super::*;
+
+/// Sidecar subtitle file of a video, e.g. 'Movie.en.srt' next to 'Movie.mkv'
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Subtitle {
+    // e.g. 'en' or 'en.forced', None for 'Movie.srt'
+    pub language: Option<String>,
+    // relative filepath, served converted to WebVTT
+    pub filepath: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ApiSubtitle<'a> {
+    language: Option<&'a str>,
+    #[serde(serialize_with = "serialize_subtitle_url")]
+    url: &'a str,
+}
+
+impl Subtitle {
+    pub(super) fn to_api(&self) -> ApiSubtitle<'_> {
+        ApiSubtitle {
+            language: self.language.as_deref(),
+            url: &self.filepath,
+        }
+    }
+
+    /// Subtitle files located next to a video and named after it
+    pub(super) fn find(base: &Path, video_fp: &Path, otr_fps: &[PathBuf]) -> Vec<Self> {
+        let video_stem = get_filestem(video_fp);
+        otr_fps
+            .iter()
+            .filter(|fp| fp.parent() == video_fp.parent())
+            .filter(|fp| {
+                crate::subtitles::EXTENSIONS
+                    .contains(&get_extension(fp).to_ascii_lowercase().as_str())
+            })
+            .filter_map(|fp| {
+                let language = match get_filestem(fp).strip_prefix(video_stem)? {
+                    "" => None,
+                    rest => Some(rest.strip_prefix('.')?.to_owned()),
+                };
+                Some(Self {
+                    language,
+                    filepath: get_rel_path_string(fp, base)?,
+                })
+            })
+            .sorted_by(|a, b| a.language.cmp(&b.language))
+            .collect()
+    }
+
+    fn label(&self) -> &str {
+        self.language.as_deref().unwrap_or("default")
+    }
+
+    /// <track> element of the player page
+    pub(super) fn to_track_html_string(&self) -> String {
+        format!(
+            "<track kind=\"subtitles\" src=\"{}\" label=\"{}\"{} />",
+            subtitle_url(&self.filepath),
+            html::attr(self.label()),
+            display(
+                self.language
+                    .as_deref()
+                    .and_then(|language| language.split('.').next())
+                    .map(html::attr),
+                " srclang=\"",
+                "\"",
+                ""
+            ),
+        )
+    }
+
+    /// Languages listed on a card, e.g. 'CC en, fr • ', empty if there are no subtitles
+    pub(super) fn to_html_string(subtitles: &[Self]) -> String {
+        match subtitles.is_empty() {
+            true => String::new(),
+            false => format!(
+                "CC {} • ",
+                html::text(&subtitles.iter().map(Self::label).join(", "))
+            ),
+        }
+    }
+}
+
+// served by the /subtitles route, which converts them on the fly
+fn subtitle_url(path: &str) -> String {
+    format!("/subtitles/{}", html::encode_path(path))
+}
+
+fn serialize_subtitle_url<S: Serializer>(path: &str, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&subtitle_url(path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn find() {
+        let base = Path::new("/lib");
+        let languages = |video_fp: &str, otr_fps: &[&str]| {
+            let otr_fps = otr_fps.iter().map(|fp| base.join(fp)).collect_vec();
+            Subtitle::find(base, &base.join(video_fp), &otr_fps)
+                .into_iter()
+                .map(|subtitle| (subtitle.language, subtitle.filepath))
+                .collect_vec()
+        };
+
+        assert_eq!(
+            languages(
+                "Movie/Movie.mkv",
+                &[
+                    "Movie/Movie.fr.ass",
+                    "Movie/Movie.srt",
+                    "Movie/Movie.en.forced.SRT",
+                    "Movie/Movie 2.srt",
+                    "Movie/Movie.en.txt",
+                    "Movie/Subs/Movie.de.srt",
+                ]
+            ),
+            vec![
+                (None, "Movie/Movie.srt".to_string()),
+                (
+                    Some("en.forced".to_string()),
+                    "Movie/Movie.en.forced.SRT".to_string()
+                ),
+                (Some("fr".to_string()), "Movie/Movie.fr.ass".to_string()),
+            ]
+        );
+
+        // episodes only get the subtitles of their own season directory
+        assert_eq!(
+            languages(
+                "Show/Season 02/S02E01.mkv",
+                &[
+                    "Show/Season 01/S02E01.en.srt",
+                    "Show/Season 02/S02E01.en.srt",
+                    "Show/Season 02/S02E010.en.srt",
+                    "Show/Season 02/S02E02.en.srt",
+                ]
+            ),
+            vec![(
+                Some("en".to_string()),
+                "Show/Season 02/S02E01.en.srt".to_string()
+            )]
+        );
+    }
+}
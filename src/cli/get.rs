@@ -1,26 +1,142 @@
-use std::io::Write;
+use std::io::{BufRead, Write};
 
 use super::*;
-use reqwest::Client;
+use crate::card::cards::card_dirs;
+use crate::card::info::{EpisodeInfo, Info};
+use crate::card::Card;
+use crate::metadata::http::HttpClient;
+use crate::metadata::{self, Candidate, EpisodeMetadata, Metadata, Provider};
+use crate::utils::write_atomic;
+use itertools::Itertools;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::{sync::Semaphore, task::JoinSet, time::Instant};
+use tracing::info;
 
 pub(super) fn subcommand() -> Command {
     Command::new("get")
         .arg(
             Arg::new("path")
                 .index(1)
-                .required(true)
+                .required_unless_present("all")
+                .conflicts_with("all")
                 .action(ArgAction::Set)
                 .value_parser(clap::value_parser!(PathBuf)),
         )
         .arg(
             Arg::new("link")
                 .index(2)
-                .required(true)
+                .help("Link or id of the title, searched by folder name if omitted")
+                .conflicts_with("all")
                 .action(ArgAction::Set),
         )
+        .arg(
+            Arg::new("provider")
+                .long("provider")
+                .help("Metadata provider to use, detected from the link or IMDb by default")
+                .action(ArgAction::Set)
+                .value_parser(Provider::NAMES),
+        )
+        .arg(
+            Arg::new("all")
+                .long("all")
+                .help("Fetch the metadata of every card in the target directory")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("missing-only")
+                .long("missing-only")
+                .help("Skip the cards that already have a description, year and thumbnail")
+                .requires("all")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("jobs")
+                .long("jobs")
+                .short('j')
+                .help("Number of cards fetched at once, overrides 'metadata_jobs'")
+                .requires("all")
+                .action(ArgAction::Set)
+                .value_parser(clap::value_parser!(u16).range(1..)),
+        )
+        .arg(
+            Arg::new("no-cache")
+                .long("no-cache")
+                .help("Bypass the cache of metadata responses")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("dry-run")
+                .long("dry-run")
+                .help("Print what would be written and how it differs, without writing anything")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("keep-existing")
+                .long("keep-existing")
+                .help("Don't replace the fields that are already set")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("only")
+                .long("only")
+                .help("Comma separated list of the fields to write")
+                .action(ArgAction::Set)
+                .value_delimiter(',')
+                .value_parser(Field::NAMES),
+        )
+        .arg(
+            Arg::new("episodes")
+                .long("episodes")
+                .help("Also fetch the title, description and air date of the episodes of shows, tmdb and omdb only")
+                .conflicts_with("only")
+                .action(ArgAction::SetTrue),
+        )
 }
 
 pub(super) fn process(arg_matches: &ArgMatches) -> eyre::Result<()> {
+    let target_dir = gcfg()
+        .target_dir
+        .as_ref()
+        .ok_or_eyre("No target directory set")?;
+
+    let provider = match (
+        arg_matches.get_one::<String>("provider"),
+        arg_matches.get_one::<String>("link"),
+    ) {
+        (Some(name), _) => Provider::from_name(name)?,
+        (None, Some(link)) => Provider::detect(link)?,
+        (None, None) => Provider::from_name("imdb")?,
+    };
+
+    let options = Options::from_arg_matches(arg_matches);
+    if options.episodes && !provider.has_episodes() {
+        return Err(eyre::eyre!(
+            "IMDb doesn't list episodes, use --provider tmdb or omdb with --episodes"
+        ));
+    }
+    let use_cache = !arg_matches.get_flag("no-cache");
+
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()?;
+
+    if arg_matches.get_flag("all") {
+        let jobs = arg_matches
+            .get_one::<u16>("jobs")
+            .map_or(gcfg().metadata_jobs, |jobs| usize::from(*jobs));
+        if jobs == 0 {
+            return Err(eyre::eyre!("'metadata_jobs' must be at least 1"));
+        }
+        let target_dir = std::fs::canonicalize(target_dir)?;
+        let mut dirs = card_dirs(&target_dir)?;
+        if arg_matches.get_flag("missing-only") {
+            dirs.retain(|dir| is_missing_metadata(dir));
+        }
+        return runtime.block_on(process_all(provider, dirs, jobs, options, use_cache));
+    }
+
     let path = arg_matches
         .get_one::<PathBuf>("path")
         .ok_or_eyre("Failed to get path")?;
@@ -29,108 +145,561 @@ pub(super) fn process(arg_matches: &ArgMatches) -> eyre::Result<()> {
     if !path.is_dir() {
         return Err(eyre::eyre!("Path is not an existing directory"));
     }
-    if path
-        .parent()
-        .ok_or_eyre("Invalid path, no parent directory found")?
-        != gcfg()
-            .target_dir
-            .as_ref()
-            .ok_or_eyre("No target directory set")?
-    {
+    if !path.starts_with(target_dir) || path == *target_dir {
         return Err(eyre::eyre!(
-            "Invalid path, parent directory does not match target directory"
+            "Invalid path, directory is not located inside the target directory"
         ));
     }
 
-    let link = arg_matches
-        .get_one::<String>("link")
-        .ok_or_eyre("Failed to get link")?;
+    let link = arg_matches.get_one::<String>("link");
 
-    tokio::runtime::Builder::new_multi_thread()
-        .enable_all()
-        .build()?
-        .block_on(async move {
-            let client = Client::builder()
-                .connection_verbose(true)
-                .connect_timeout(gcfg().connection_timeout)
-                .user_agent(&gcfg().user_agent)
-                .build()?;
-
-            let response = client.get(link).send().await?;
-            let text = response.text().await?;
-
-            let mut sidx = text
-                .find(&gcfg().imdb_description_start_match)
-                .ok_or_eyre("Failed to find a match for the start of the description")?;
-            sidx += gcfg().imdb_description_start_match.len();
-
-            let offset = text[sidx..]
-                .find(&gcfg().imdb_description_end_match)
-                .ok_or_eyre("Failed to find a match for the end of the description")?;
-
-            let description = &text[sidx..sidx + offset];
-
-            std::fs::OpenOptions::new()
-                .write(true)
-                .truncate(true)
-                .create(true)
-                .open(path.join(".description"))?
-                .write_all(description.as_bytes())?;
-
-            let mut sidx = text
-                .find(&gcfg().imdb_year_start_match)
-                .ok_or_eyre("Failed to find a match for the start of the year")?;
-            sidx += gcfg().imdb_year_start_match.len();
-
-            let offset = text[sidx..]
-                .find(&gcfg().imdb_year_end_match)
-                .ok_or_eyre("Failed to find a match for the end of the year")?;
-
-            let year = &text[sidx..sidx + offset];
-
-            std::fs::OpenOptions::new()
-                .write(true)
-                .truncate(true)
-                .create(true)
-                .open(path.join(".year"))?
-                .write_all(year.as_bytes())?;
-
-            let mut sidx = text
-                .find(&gcfg().imdb_image_redirect_start_match)
-                .ok_or_eyre("Failed to find a match for the start of the image redirect")?;
-            sidx += gcfg().imdb_image_redirect_start_match.len();
-
-            let offset = text[sidx..]
-                .find(&gcfg().imdb_image_redirect_end_match)
-                .ok_or_eyre("Failed to find a match for the end of the image redirect")?;
-
-            let image_redirect = gcfg().imdb_url.clone() + &text[sidx..sidx + offset];
-
-            let response = client.get(&image_redirect).send().await?;
-            let text = response.text().await?;
-
-            let sidx = text
-                .find(&gcfg().imdb_image_start_match)
-                .ok_or_eyre("Failed to find a match for the start of the image")?;
-
-            let offset = text[sidx..]
-                .find(&gcfg().imdb_image_end_match)
-                .ok_or_eyre("Failed to find a match for the end of the image")?;
-
-            let image = &text[sidx..sidx + offset];
-
-            let response = client.get(image).send().await?;
-            let image = response.bytes().await?;
-
-            std::fs::OpenOptions::new()
-                .write(true)
-                .truncate(true)
-                .create(true)
-                .open(path.join(".thumbnail"))?
-                .write_all(&image)?;
-
-            Ok::<(), eyre::Error>(())
-        })?;
+    runtime.block_on(async move {
+        let client = HttpClient::from_config(use_cache)?;
+        let limiter = RateLimiter::new(gcfg().metadata_request_interval);
+
+        let (link, search) = match link {
+            Some(link) => (link.clone(), false),
+            None => {
+                let (query, year) = metadata::query_from_folder_name(&path);
+                info!("Searching for '{query}'");
+                let candidates = provider.search(&client, &query, year.as_deref()).await?;
+                let Some(candidate) = choose(candidates)? else {
+                    return Ok(());
+                };
+                (candidate.link, true)
+            }
+        };
+
+        limiter.wait().await;
+        let mut metadata = provider.fetch(&client, &link).await?;
+        info!("Found {metadata}");
+        if options.episodes {
+            metadata.episodes = fetch_episodes(&client, &provider, &limiter, &path, &link).await?;
+        }
+
+        write_metadata(&client, &limiter, &path, metadata, &options, search).await
+    })
+}
+
+// Result of the metadata update of a single card in batch mode
+enum Outcome {
+    Updated(Metadata),
+    Ambiguous(Vec<Candidate>),
+    Failed(eyre::Report),
+}
+
+// Searches and updates every card concurrently, then logs a summary
+async fn process_all(
+    provider: Provider,
+    dirs: Vec<PathBuf>,
+    jobs: usize,
+    options: Options,
+    use_cache: bool,
+) -> eyre::Result<()> {
+    info!(
+        "Fetching the metadata of {} cards, {jobs} at a time",
+        dirs.len()
+    );
+
+    let client = HttpClient::from_config(use_cache)?;
+    let provider = Arc::new(provider);
+    let limiter = Arc::new(RateLimiter::new(gcfg().metadata_request_interval));
+    let semaphore = Arc::new(Semaphore::new(jobs));
+    let options = Arc::new(options);
+
+    let mut tasks = JoinSet::new();
+    for dir in dirs {
+        let (client, provider, limiter, semaphore, options) = (
+            client.clone(),
+            provider.clone(),
+            limiter.clone(),
+            semaphore.clone(),
+            options.clone(),
+        );
+        tasks.spawn(async move {
+            let _permit = semaphore.acquire_owned().await;
+            let outcome = update_from_search(&client, &provider, &limiter, &options, &dir).await;
+            (dir, outcome)
+        });
+    }
+
+    let mut updated = 0;
+    let mut ambiguous: Vec<(PathBuf, Vec<Candidate>)> = Vec::new();
+    let mut failed: Vec<(PathBuf, eyre::Report)> = Vec::new();
+    while let Some(result) = tasks.join_next().await {
+        let (dir, outcome) = result?;
+        match outcome {
+            Outcome::Updated(metadata) => {
+                info!("'{}' updated with {metadata}", dir.display());
+                updated += 1;
+            }
+            Outcome::Ambiguous(candidates) => ambiguous.push((dir, candidates)),
+            Outcome::Failed(err) => failed.push((dir, err)),
+        }
+    }
+
+    info!(
+        "Done, {updated} {}, {} ambiguous, {} failed",
+        if options.dry_run {
+            "would be updated"
+        } else {
+            "updated"
+        },
+        ambiguous.len(),
+        failed.len()
+    );
+    ambiguous.sort_by(|a, b| a.0.cmp(&b.0));
+    for (dir, candidates) in ambiguous {
+        warn!(
+            "'{}' is ambiguous, run 'get' on it to pick one of: {}",
+            dir.display(),
+            candidates.iter().take(3).join(", ")
+        );
+    }
+    failed.sort_by(|a, b| a.0.cmp(&b.0));
+    for (dir, err) in failed {
+        warn!("'{}' failed, '{err}'", dir.display());
+    }
 
     Ok(())
 }
+
+async fn update_from_search(
+    client: &HttpClient,
+    provider: &Provider,
+    limiter: &RateLimiter,
+    options: &Options,
+    dir: &Path,
+) -> Outcome {
+    let (query, year) = metadata::query_from_folder_name(dir);
+
+    limiter.wait().await;
+    let candidates = match provider.search(client, &query, year.as_deref()).await {
+        Ok(candidates) if candidates.is_empty() => {
+            return Outcome::Failed(eyre::eyre!("No results found for '{query}'"))
+        }
+        Ok(candidates) => candidates,
+        Err(err) => return Outcome::Failed(err),
+    };
+    let Some(candidate) = metadata::best_match(&candidates) else {
+        return Outcome::Ambiguous(candidates);
+    };
+
+    let result = async {
+        limiter.wait().await;
+        let mut metadata = provider.fetch(client, &candidate.link).await?;
+        if options.episodes {
+            metadata.episodes =
+                fetch_episodes(client, provider, limiter, dir, &candidate.link).await?;
+        }
+        write_metadata(client, limiter, dir, metadata.clone(), options, true).await?;
+        Ok::<Metadata, eyre::Report>(metadata)
+    };
+    match result.await {
+        Ok(metadata) => Outcome::Updated(metadata),
+        Err(err) => Outcome::Failed(err),
+    }
+}
+
+// Fetches the episode listing of every season found in a show, nothing for other cards.
+// Seasons unknown to the provider are skipped, it only fails if none of them could be fetched
+async fn fetch_episodes(
+    client: &HttpClient,
+    provider: &Provider,
+    limiter: &RateLimiter,
+    dir: &Path,
+    link: &str,
+) -> eyre::Result<Vec<EpisodeMetadata>> {
+    // only the seasons are needed, the card directory serves as the base
+    let seasons = Card::from_path(dir, dir)?.seasons();
+    let mut episodes = Vec::new();
+    let mut fetched = false;
+    let mut error = None;
+    for season in seasons {
+        limiter.wait().await;
+        match provider.episodes(client, link, season).await {
+            Ok(season_episodes) => {
+                episodes.extend(season_episodes);
+                fetched = true;
+            }
+            Err(err) => {
+                warn!("{} - skipping season {season}, '{err}'", dir.display());
+                error = Some(err);
+            }
+        }
+    }
+    match error {
+        Some(err) if !fetched => Err(err),
+        _ => Ok(episodes),
+    }
+}
+
+// Fields of a card written by this subcommand
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Field {
+    Title,
+    Description,
+    Year,
+    Thumbnail,
+    Genres,
+    Runtime,
+    Rating,
+}
+
+impl Field {
+    const ALL: [Self; 7] = [
+        Self::Title,
+        Self::Description,
+        Self::Year,
+        Self::Thumbnail,
+        Self::Genres,
+        Self::Runtime,
+        Self::Rating,
+    ];
+    const NAMES: [&'static str; 7] = [
+        "title",
+        "description",
+        "year",
+        "thumbnail",
+        "genres",
+        "runtime",
+        "rating",
+    ];
+
+    fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|field| field.name() == name)
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Title => "title",
+            Self::Description => "description",
+            Self::Year => "year",
+            Self::Thumbnail => "thumbnail",
+            Self::Genres => "genres",
+            Self::Runtime => "runtime",
+            Self::Rating => "rating",
+        }
+    }
+
+    // the details are only written when the provider knows them
+    fn is_required(self) -> bool {
+        !matches!(self, Self::Genres | Self::Runtime | Self::Rating)
+    }
+
+    fn current(self, info: &Info) -> Option<String> {
+        match self {
+            Self::Title => info.title.clone(),
+            Self::Description => info.description.clone(),
+            Self::Year => info.year.clone(),
+            Self::Thumbnail => info.thumbnail.clone(),
+            Self::Genres => {
+                Some(info.details.genres.join(", ")).filter(|genres| !genres.is_empty())
+            }
+            Self::Runtime => info.details.runtime.map(|runtime| runtime.to_string()),
+            Self::Rating => info.details.rating.map(|rating| rating.to_string()),
+        }
+    }
+
+    // the thumbnail is the url of the image
+    fn value(self, metadata: &Metadata) -> Option<String> {
+        match self {
+            Self::Title => metadata.title.clone(),
+            Self::Description => metadata.description.clone(),
+            Self::Year => metadata.year.clone(),
+            Self::Thumbnail => metadata.image_url.clone(),
+            Self::Genres => Some(metadata.genres.join(", ")).filter(|genres| !genres.is_empty()),
+            Self::Runtime => metadata.runtime.map(|runtime| runtime.to_string()),
+            Self::Rating => metadata.rating.map(|rating| rating.to_string()),
+        }
+    }
+
+    fn set(self, info: &mut Info, value: String) {
+        match self {
+            Self::Title => info.title = Some(value),
+            Self::Description => info.description = Some(value),
+            Self::Year => info.year = Some(value),
+            Self::Thumbnail => info.thumbnail = Some(value),
+            Self::Genres => {
+                info.details.genres = value.split(", ").map(str::to_owned).collect();
+            }
+            Self::Runtime => info.details.runtime = value.parse().ok(),
+            Self::Rating => info.details.rating = value.parse().ok(),
+        }
+    }
+}
+
+// Controls which fields are written and whether anything is written at all
+#[derive(Debug, Default)]
+struct Options {
+    // every field by default, the title only when it comes from a search
+    only: Option<Vec<Field>>,
+    keep_existing: bool,
+    dry_run: bool,
+    // per-episode metadata of shows, opt-in as it takes a request per season
+    episodes: bool,
+}
+
+impl Options {
+    fn from_arg_matches(arg_matches: &ArgMatches) -> Self {
+        Self {
+            only: arg_matches.get_many::<String>("only").map(|names| {
+                names
+                    .filter_map(|name| Field::from_name(name))
+                    .unique_by(|field| field.name())
+                    .collect()
+            }),
+            keep_existing: arg_matches.get_flag("keep-existing"),
+            dry_run: arg_matches.get_flag("dry-run"),
+            episodes: arg_matches.get_flag("episodes"),
+        }
+    }
+
+    fn fields(&self, search: bool) -> Vec<Field> {
+        match &self.only {
+            Some(fields) => fields.clone(),
+            None => Field::ALL
+                .into_iter()
+                .filter(|field| search || *field != Field::Title)
+                .collect(),
+        }
+    }
+}
+
+// Writes the selected fields to the '.silvus.json' of a card, or prints what would be
+// written on a dry run
+async fn write_metadata(
+    client: &HttpClient,
+    limiter: &RateLimiter,
+    path: &Path,
+    metadata: Metadata,
+    options: &Options,
+    search: bool,
+) -> eyre::Result<()> {
+    let mut info = Info::load_dir(path)?;
+
+    let mut changes: Vec<(Field, Option<String>, String)> = Vec::new();
+    for field in options.fields(search) {
+        let current = field.current(&info);
+        if options.keep_existing && current.is_some() {
+            continue;
+        }
+        match field.value(&metadata) {
+            Some(value) => changes.push((field, current, value)),
+            None if field.is_required() => {
+                return Err(eyre::eyre!("Failed to find a {}", field.name()))
+            }
+            None => (),
+        }
+    }
+
+    let episodes = metadata
+        .episodes
+        .into_iter()
+        .map(EpisodeInfo::from)
+        .collect_vec();
+    let write_episodes = options.episodes
+        && !episodes.is_empty()
+        && (!options.keep_existing || info.episodes.is_empty());
+
+    if options.dry_run {
+        let mut report = format!("{}\n", path.display());
+        if changes.is_empty() && !write_episodes {
+            report.push_str("  nothing to write\n");
+        }
+        for (field, current, value) in changes {
+            report.push_str(&describe_change(field, current.as_deref(), &value));
+        }
+        if write_episodes {
+            report.push_str(&format!(
+                "  episodes: {} -> {} episodes\n",
+                info.episodes.len(),
+                episodes.len()
+            ));
+        }
+        print!("{report}");
+        return Ok(());
+    }
+
+    // everything is fetched before anything is written, so that a card is never half-updated
+    let mut thumbnail = None;
+    for (field, _, value) in changes {
+        match field {
+            Field::Thumbnail => {
+                limiter.wait().await;
+                thumbnail = Some(client.get(&value, &[]).await?);
+            }
+            field => field.set(&mut info, value),
+        }
+    }
+    if let Some(bytes) = thumbnail {
+        let filename = info
+            .thumbnail
+            .clone()
+            .unwrap_or_else(|| ".thumbnail".to_owned());
+        write_atomic(&path.join(&filename), &bytes)?;
+        info.thumbnail = Some(filename);
+    }
+    if write_episodes {
+        info.episodes = episodes;
+    }
+    info.save(path)
+}
+
+// Human readable summary of the change of a single field
+fn describe_change(field: Field, current: Option<&str>, value: &str) -> String {
+    let header = match current {
+        Some(_) => format!("  {}\n", field.name()),
+        None => format!("  {} (new)\n", field.name()),
+    };
+    if field == Field::Thumbnail {
+        return format!("{header}    + download {value}\n");
+    }
+    let current = current.unwrap_or_default();
+    if current == value.trim() {
+        return format!("  {} unchanged\n", field.name());
+    }
+    header
+        + &diff(current, value)
+            .lines()
+            .map(|line| format!("    {line}\n"))
+            .collect::<String>()
+}
+
+/// Line based diff, removed lines are prefixed with '-', added ones with '+'
+fn diff(old: &str, new: &str) -> String {
+    let old = old.lines().collect_vec();
+    let new = new.lines().collect_vec();
+
+    // lengths of the longest common subsequences of every pair of suffixes
+    let mut lcs = vec![vec![0_usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i][j] = if old[i] == new[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut output = String::new();
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            output.push_str(&format!("  {}\n", old[i]));
+            i += 1;
+            j += 1;
+        } else if i < old.len() && (j == new.len() || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            output.push_str(&format!("- {}\n", old[i]));
+            i += 1;
+        } else {
+            output.push_str(&format!("+ {}\n", new[j]));
+            j += 1;
+        }
+    }
+    output
+}
+
+// A card is missing metadata if it lacks any of the required fields or its thumbnail file
+fn is_missing_metadata(dir: &Path) -> bool {
+    Info::load_dir(dir).map_or(true, |info| {
+        info.description.is_none()
+            || info.year.is_none()
+            || !info
+                .thumbnail
+                .is_some_and(|thumbnail| dir.join(thumbnail).is_file())
+    })
+}
+
+// Spaces out requests so that providers aren't hammered, shared between tasks
+struct RateLimiter {
+    interval: Duration,
+    next: tokio::sync::Mutex<Instant>,
+}
+
+impl RateLimiter {
+    fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            next: tokio::sync::Mutex::new(Instant::now()),
+        }
+    }
+
+    /// Waits for the next free slot
+    async fn wait(&self) {
+        let slot = {
+            let mut next = self.next.lock().await;
+            let slot = (*next).max(Instant::now());
+            *next = slot + self.interval;
+            slot
+        };
+        tokio::time::sleep_until(slot).await;
+    }
+}
+
+// Lists the best candidates and reads the choice of the user, None if aborted
+fn choose(candidates: Vec<Candidate>) -> eyre::Result<Option<Candidate>> {
+    if candidates.is_empty() {
+        return Err(eyre::eyre!("No results found, pass a link instead"));
+    }
+    let mut candidates: Vec<Candidate> = candidates.into_iter().take(10).collect();
+    for (idx, candidate) in candidates.iter().enumerate() {
+        println!("{:>2}. {candidate}", idx + 1);
+    }
+
+    let stdin = std::io::stdin();
+    loop {
+        print!("Select a result [1], 0 to abort: ");
+        std::io::stdout().flush()?;
+        let mut input = String::new();
+        if stdin.lock().read_line(&mut input)? == 0 {
+            return Ok(None);
+        }
+        match input.trim() {
+            "" => return Ok(Some(candidates.swap_remove(0))),
+            "0" | "q" => return Ok(None),
+            input => match input.parse::<usize>() {
+                Ok(idx) if (1..=candidates.len()).contains(&idx) => {
+                    return Ok(Some(candidates.swap_remove(idx - 1)))
+                }
+                _ => println!("Invalid choice '{input}'"),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn field_names() {
+        for (field, name) in Field::ALL.into_iter().zip(Field::NAMES) {
+            assert_eq!(field.name(), name);
+            assert_eq!(Field::from_name(name), Some(field));
+        }
+    }
+
+    #[test]
+    fn missing_metadata() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        std::fs::write(
+            dir.join(".silvus.json"),
+            r#"{ "description": "A film", "year": "1999", "thumbnail": ".thumbnail.jpg" }"#,
+        )
+        .unwrap();
+        assert!(is_missing_metadata(dir));
+        std::fs::write(dir.join(".thumbnail.jpg"), "").unwrap();
+        assert!(!is_missing_metadata(dir));
+    }
+
+    #[test]
+    fn line_diff() {
+        assert_eq!(diff("a\nb\nc", "a\nc\nd"), "  a\n- b\n  c\n+ d\n");
+        assert_eq!(diff("", "new"), "+ new\n");
+        assert_eq!(diff("old", "new"), "- old\n+ new\n");
+        assert_eq!(diff("same", "same"), "  same\n");
+    }
+}
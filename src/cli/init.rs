@@ -1,11 +1,21 @@
 use std::{io::Write, path::Path};
 
 use super::*;
+use tracing::info;
 
 static DOWNLOAD_IMAGE: &[u8] = include_bytes!("../../assets/download.svg");
+static PLAY_IMAGE: &[u8] = include_bytes!("../../assets/play.svg");
 static DEFAULT_THUMBNAIL_IMAGE: &[u8] = include_bytes!("../../assets/default_thumbnail.png");
 static ICON_IMAGE: &[u8] = include_bytes!("../../assets/icon.svg");
 
+// files of the '.assets' directory of the target directory, served by the /res route
+static ASSETS: [(&str, &[u8]); 4] = [
+    ("download.svg", DOWNLOAD_IMAGE),
+    ("play.svg", PLAY_IMAGE),
+    ("default_thumbnail.png", DEFAULT_THUMBNAIL_IMAGE),
+    ("icon.svg", ICON_IMAGE),
+];
+
 pub(super) fn subcommand() -> Command {
     Command::new("init").arg(
         Arg::new("path")
@@ -32,13 +42,9 @@ pub(super) fn process(arg_matches: &ArgMatches) -> eyre::Result<()> {
 
     let asset_path = path.join(".assets/");
     crate::utils::check_or_create_all(&asset_path)?;
-    save_to_file(&asset_path, "download.svg", DOWNLOAD_IMAGE)?;
-    save_to_file(
-        &asset_path,
-        "default_thumbnail.png",
-        DEFAULT_THUMBNAIL_IMAGE,
-    )?;
-    save_to_file(&asset_path, "icon.svg", ICON_IMAGE)?;
+    for (filename, data) in ASSETS {
+        save_to_file(&asset_path, filename, data)?;
+    }
 
     let mut owned_config = crate::config::get().clone();
     owned_config.target_dir = Some(path);
@@ -46,6 +52,20 @@ pub(super) fn process(arg_matches: &ArgMatches) -> eyre::Result<()> {
     Ok(())
 }
 
+/// Adds the assets missing from the target directory, e.g. the ones introduced after it was
+/// initialized, existing files are left as they are
+pub(super) fn write_missing_assets(target_dir: &Path) -> eyre::Result<()> {
+    let asset_path = target_dir.join(".assets/");
+    crate::utils::check_or_create_all(&asset_path)?;
+    for (filename, data) in ASSETS {
+        if !asset_path.join(filename).exists() {
+            info!("Adding missing asset '{filename}'");
+            save_to_file(&asset_path, filename, data)?;
+        }
+    }
+    Ok(())
+}
+
 fn save_to_file(path: &Path, filename: &str, data: &[u8]) -> eyre::Result<()> {
     let fp = path.join(filename);
     Ok(std::fs::OpenOptions::new()
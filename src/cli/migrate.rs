@@ -0,0 +1,88 @@
+use super::*;
+use crate::card::{cards::card_dirs, info::Info};
+use crate::utils::{get_filename, get_filestem};
+use tracing::info;
+
+pub(super) fn subcommand() -> Command {
+    Command::new("migrate-metadata")
+        .about("Converts the legacy dot-files of every card into a single '.silvus.json'")
+        .arg(
+            Arg::new("keep-legacy")
+                .long("keep-legacy")
+                .help("Don't delete the legacy dot-files once converted")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("dry-run")
+                .long("dry-run")
+                .help("Print the cards that would be migrated, without writing anything")
+                .action(ArgAction::SetTrue),
+        )
+}
+
+pub(super) fn process(arg_matches: &ArgMatches) -> eyre::Result<()> {
+    let keep_legacy = arg_matches.get_flag("keep-legacy");
+    let dry_run = arg_matches.get_flag("dry-run");
+
+    let target_dir = gcfg()
+        .target_dir
+        .as_ref()
+        .ok_or_eyre("No target directory set")?;
+    let target_dir = std::fs::canonicalize(target_dir)?;
+
+    let (mut migrated, mut skipped, mut failed) = (0, 0, 0);
+    for dir in card_dirs(&target_dir)? {
+        match migrate(&dir, keep_legacy, dry_run) {
+            Ok(true) => migrated += 1,
+            Ok(false) => skipped += 1,
+            Err(err) => {
+                warn!("Failed to migrate '{}', '{err}'", dir.display());
+                failed += 1;
+            }
+        }
+    }
+
+    info!(
+        "Done, {migrated} {}, {skipped} already up to date, {failed} failed",
+        if dry_run { "to migrate" } else { "migrated" }
+    );
+    Ok(())
+}
+
+// Returns false if the card has nothing to migrate
+fn migrate(dir: &Path, keep_legacy: bool, dry_run: bool) -> eyre::Result<bool> {
+    let dot_fps = std::fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|fp| fp.is_file() && get_filename(fp).starts_with('.'))
+        .collect_vec();
+    let legacy_fps = dot_fps
+        .iter()
+        .filter(|fp| Info::LEGACY_STEMS.contains(&get_filestem(fp)))
+        .collect_vec();
+
+    let has_info = dir.join(Info::FILENAME).is_file();
+    let info = Info::load(dir, &dot_fps);
+    // a lone thumbnail still gets referenced from a new '.silvus.json'
+    if legacy_fps.is_empty() && (has_info || info == Info::default()) {
+        return Ok(false);
+    }
+
+    if dry_run {
+        println!(
+            "{} ({})",
+            dir.display(),
+            legacy_fps.iter().map(|fp| get_filename(fp)).join(", ")
+        );
+        return Ok(true);
+    }
+
+    info.save(dir)?;
+    if !keep_legacy {
+        for fp in legacy_fps {
+            std::fs::remove_file(fp)?;
+        }
+    }
+    info!("Migrated '{}'", dir.display());
+    Ok(true)
+}
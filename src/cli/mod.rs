@@ -1,25 +1,47 @@
 // Modules
 mod get;
 mod init;
+mod migrate;
+mod nfo;
 mod run;
+mod scan;
+mod user;
 
 // Imports
-use crate::config::get as gcfg;
+use crate::config::{get as gcfg, Config};
 use clap::{Arg, ArgAction, ArgMatches, Command};
 use eyre::{Context, OptionExt};
-use std::path::PathBuf;
+use itertools::Itertools;
+use std::path::{Path, PathBuf};
 use tracing::warn;
 
 pub fn cli() -> eyre::Result<()> {
-    let command =
-        clap::command!().subcommands([init::subcommand(), run::subcommand(), get::subcommand()]);
+    let command = clap::command!().subcommands([
+        init::subcommand(),
+        run::subcommand(),
+        get::subcommand(),
+        user::subcommand(),
+        migrate::subcommand(),
+        nfo::subcommand(),
+        scan::subcommand(),
+    ]);
 
     let arg_matches = command.get_matches();
 
+    let config = match arg_matches.subcommand() {
+        Some(("run", arg_matches)) => run::config(arg_matches)?,
+        _ => Config::load(),
+    };
+    crate::config::init(config)?;
+
     match arg_matches.subcommand() {
         Some(("init", arg_matches)) => init::process(arg_matches),
         Some(("run", arg_matches)) => run::process(arg_matches),
         Some(("get", arg_matches)) => get::process(arg_matches),
+        Some(("user", arg_matches)) => user::process(arg_matches),
+        Some(("migrate-metadata", arg_matches)) => migrate::process(arg_matches),
+        Some(("export-nfo", arg_matches)) => nfo::process(arg_matches),
+        Some(("scan", arg_matches)) => scan::process(arg_matches),
         _ => Ok(()),
     }
 }
@@ -0,0 +1,74 @@
+use super::*;
+use crate::card::{cards::card_dirs, info::Info, nfo::Nfo, Card};
+use crate::utils::write_atomic;
+use tracing::info;
+
+pub(super) fn subcommand() -> Command {
+    Command::new("export-nfo")
+        .about("Writes a Kodi/Jellyfin NFO file for every movie and show from its metadata")
+        .arg(
+            Arg::new("overwrite")
+                .long("overwrite")
+                .help("Replace the NFO files that already exist")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("dry-run")
+                .long("dry-run")
+                .help("Print the NFO files that would be written, without writing anything")
+                .action(ArgAction::SetTrue),
+        )
+}
+
+pub(super) fn process(arg_matches: &ArgMatches) -> eyre::Result<()> {
+    let overwrite = arg_matches.get_flag("overwrite");
+    let dry_run = arg_matches.get_flag("dry-run");
+
+    let target_dir = gcfg()
+        .target_dir
+        .as_ref()
+        .ok_or_eyre("No target directory set")?;
+    let target_dir = std::fs::canonicalize(target_dir)?;
+
+    let (mut exported, mut skipped, mut failed) = (0, 0, 0);
+    for dir in card_dirs(&target_dir)? {
+        let result = Card::from_path(&target_dir, &dir).and_then(|card| {
+            let (root, filename) = match card {
+                Card::Movie(_) => Nfo::MOVIE,
+                Card::Show(_) => Nfo::SHOW,
+                // Kodi has no equivalent
+                Card::Other(_) => return Ok(false),
+            };
+            let fp = dir.join(filename);
+            if fp.exists() && !overwrite {
+                return Ok(false);
+            }
+
+            let mut info = Info::load_dir(&dir)?;
+            // the title is always set in the NFO, Kodi would otherwise use the file name
+            info.title = Some(info.title_or_folder_name(&dir));
+            let xml = Nfo::from_info(&info).to_xml(root)?;
+            if dry_run {
+                println!("{}\n{xml}", fp.display());
+            } else {
+                write_atomic(&fp, xml.as_bytes())?;
+            }
+            Ok(true)
+        });
+
+        match result {
+            Ok(true) => exported += 1,
+            Ok(false) => skipped += 1,
+            Err(err) => {
+                warn!("Failed to export '{}', '{err}'", dir.display());
+                failed += 1;
+            }
+        }
+    }
+
+    info!(
+        "Done, {exported} {}, {skipped} skipped, {failed} failed",
+        if dry_run { "to export" } else { "exported" }
+    );
+    Ok(())
+}
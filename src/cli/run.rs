@@ -1,24 +1,108 @@
+// Modules
+mod api;
+mod auth;
+mod res;
+mod subtitles;
+mod tls;
+mod watch;
+
 // Imports
 use super::*;
-use crate::card::cards::Cards;
+use crate::auth::Sessions;
+use crate::library::{self, Library, SharedLibrary};
+use crate::progress::ProgressStore;
 use axum::{
+    extract::State,
+    middleware,
     response::{Html, IntoResponse},
     routing::get,
     Router,
 };
-use std::sync::OnceLock;
+use axum_macros::FromRef;
+use socket2::{Domain, Socket, Type};
+use std::{
+    future::IntoFuture,
+    net::{IpAddr, SocketAddr},
+};
+use tokio::task::JoinSet;
 use tower_http::{services::ServeDir, trace::TraceLayer};
 
-static HTML_PAGE: OnceLock<&'static str> = OnceLock::new();
+#[derive(Clone, FromRef)]
+struct AppState {
+    library: SharedLibrary,
+    sessions: Sessions,
+    progress: ProgressStore,
+}
 
 pub(super) fn subcommand() -> Command {
     Command::new("run")
+        .arg(
+            Arg::new("bind")
+                .long("bind")
+                .help("Address to listen on, can be repeated")
+                .action(ArgAction::Append)
+                .value_parser(clap::value_parser!(IpAddr)),
+        )
+        .arg(
+            Arg::new("port")
+                .long("port")
+                .action(ArgAction::Set)
+                .value_parser(clap::value_parser!(u16).range(1..)),
+        )
+        .arg(
+            Arg::new("target-dir")
+                .long("target-dir")
+                .action(ArgAction::Set)
+                .value_parser(clap::value_parser!(PathBuf)),
+        )
+        .arg(
+            Arg::new("config")
+                .long("config")
+                .help("Config file to use instead of the default one")
+                .action(ArgAction::Set)
+                .value_parser(clap::value_parser!(PathBuf)),
+        )
+}
+
+/// Loads the config and applies the overrides passed as arguments, for this invocation only
+pub(super) fn config(arg_matches: &ArgMatches) -> eyre::Result<Config> {
+    let mut config = match arg_matches.get_one::<PathBuf>("config") {
+        Some(path) => Config::load_from_file(path)?,
+        None => Config::load(),
+    };
+    if let Some(bind) = arg_matches.get_many::<IpAddr>("bind") {
+        config.bind = bind.copied().collect();
+    }
+    if let Some(port) = arg_matches.get_one::<u16>("port") {
+        config.port = *port;
+    }
+    if let Some(path) = arg_matches.get_one::<PathBuf>("target-dir") {
+        config.target_dir = Some(
+            path.canonicalize()
+                .wrap_err("Failed to canonicalize target directory")?,
+        );
+    }
+    config.validate()?;
+    Ok(config)
 }
 
 pub(super) fn process(_arg_matches: &ArgMatches) -> eyre::Result<()> {
-    let cards = Cards::load()?;
-    let response = cards.generate_static_html_page();
-    HTML_PAGE.set(Box::leak(response.into_boxed_str())).unwrap();
+    if let Err(err) = init::write_missing_assets(gcfg().target_dir.as_ref().unwrap()) {
+        warn!("Failed to add the missing assets to the target directory, '{err}'");
+    }
+
+    let library = SharedLibrary::new(Library::load()?);
+    let _watcher = library::watch(library.clone())?;
+
+    if gcfg().users.is_empty() {
+        warn!("No users configured, authentication is disabled");
+    }
+
+    let state = AppState {
+        library,
+        sessions: Sessions::default(),
+        progress: ProgressStore::load(),
+    };
 
     tokio::runtime::Builder::new_multi_thread()
         .enable_all()
@@ -27,19 +111,133 @@ pub(super) fn process(_arg_matches: &ArgMatches) -> eyre::Result<()> {
             let app = Router::new()
                 .layer(TraceLayer::new_for_http())
                 .route("/", get(root))
-                .nest_service(
+                .nest("/api", api::router())
+                .nest("/watch", watch::router())
+                .nest("/subtitles", subtitles::router())
+                .nest(
                     "/res",
-                    ServeDir::new(crate::config::get().target_dir.as_ref().unwrap()),
-                );
-            tracing::info!("Binding application to port {}", crate::config::get().port);
-            let address = format!("0.0.0.0:{}", crate::config::get().port);
-            let listener = tokio::net::TcpListener::bind(&address).await?;
-            axum::serve(listener, app).await?;
+                    res::router(
+                        crate::config::get().target_dir.as_ref().unwrap(),
+                        state.library.clone(),
+                    )?,
+                )
+                .layer(middleware::from_fn_with_state(state.clone(), auth::require))
+                .merge(auth::router())
+                .with_state(state);
+
+            let tls = tls::load().await?;
+            let mut servers = JoinSet::new();
+
+            if let Some(port) = gcfg().tls_redirect_port {
+                if tls.is_some() {
+                    let redirect = tls::redirect_router(gcfg().port);
+                    for ip in gcfg().bind.iter() {
+                        let listener =
+                            tokio::net::TcpListener::from_std(bind(SocketAddr::new(*ip, port))?)?;
+                        tracing::info!(
+                            "Redirecting HTTP requests on '{}' to HTTPS",
+                            listener.local_addr()?
+                        );
+                        servers.spawn(axum::serve(listener, redirect.clone()).into_future());
+                    }
+                } else {
+                    warn!("'tls_redirect_port' is set but TLS is disabled, ignoring");
+                }
+            }
+
+            for address in gcfg().listen_addresses() {
+                let listener = bind(address)?;
+                tracing::info!("Binding application to '{address}'");
+                match &tls {
+                    Some(config) => {
+                        servers.spawn(
+                            axum_server::from_tcp_rustls(listener, config.clone())
+                                .serve(app.clone().into_make_service()),
+                        );
+                    }
+                    None => {
+                        let listener = tokio::net::TcpListener::from_std(listener)?;
+                        servers.spawn(axum::serve(listener, app.clone()).into_future());
+                    }
+                }
+            }
+
+            // every listener runs until an error occurs
+            while let Some(result) = servers.join_next().await {
+                result??;
+            }
             Ok(())
         })
 }
 
-async fn root() -> impl IntoResponse {
-    let st: &'static str = HTML_PAGE.get().unwrap();
-    Html(st)
+// IPv6 sockets are made IPv6-only so that '0.0.0.0' and '::' can be bound side by side
+fn bind(address: SocketAddr) -> eyre::Result<std::net::TcpListener> {
+    let socket = Socket::new(Domain::for_address(address), Type::STREAM, None)?;
+    if address.is_ipv6() {
+        socket.set_only_v6(true)?;
+    }
+    socket.set_reuse_address(true)?;
+    socket.set_nonblocking(true)?;
+    socket
+        .bind(&address.into())
+        .wrap_err_with(|| format!("Failed to bind to '{address}'"))?;
+    socket.listen(1024)?;
+    Ok(socket.into())
+}
+
+async fn root(State(library): State<SharedLibrary>) -> impl IntoResponse {
+    Html(library.get().page.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn overrides() {
+        let dir = tempfile::tempdir().unwrap();
+        let config_fp = dir.path().join("silvus.conf");
+        std::fs::write(
+            &config_fp,
+            r#"{ "target_dir": "/nonexistent", "bind": ["0.0.0.0"], "port": 1888 }"#,
+        )
+        .unwrap();
+
+        let arg_matches = subcommand()
+            .try_get_matches_from([
+                "run",
+                "--config",
+                config_fp.to_str().unwrap(),
+                "--bind",
+                "127.0.0.1",
+                "--bind",
+                "::1",
+                "--port",
+                "8080",
+                "--target-dir",
+                dir.path().to_str().unwrap(),
+            ])
+            .unwrap();
+        let overridden = config(&arg_matches).unwrap();
+        assert_eq!(
+            overridden.target_dir,
+            Some(dir.path().canonicalize().unwrap())
+        );
+        assert_eq!(
+            overridden.listen_addresses(),
+            vec![
+                "127.0.0.1:8080".parse::<SocketAddr>().unwrap(),
+                "[::1]:8080".parse().unwrap()
+            ]
+        );
+
+        // the config file's target directory doesn't exist
+        let arg_matches = subcommand()
+            .try_get_matches_from(["run", "--config", config_fp.to_str().unwrap()])
+            .unwrap();
+        assert!(config(&arg_matches).is_err());
+        assert!(subcommand()
+            .try_get_matches_from(["run", "--port", "0"])
+            .is_err());
+    }
 }
@@ -0,0 +1,138 @@
+// Imports
+use super::*;
+use crate::card::Card;
+use crate::progress::{Progress, ProgressStore};
+use auth::Viewer;
+use axum::{extract::Path, http::StatusCode, Extension, Json};
+use serde::Deserialize;
+use std::collections::HashMap;
+
+pub(super) fn router() -> Router<AppState> {
+    Router::new()
+        .route("/cards", get(cards))
+        .route("/cards/:id", get(card))
+        .route("/cards/:id/episodes", get(episodes))
+        .route("/progress", get(progress))
+        .route(
+            "/progress/:id",
+            get(video_progress).post(update_video_progress),
+        )
+        .route(
+            "/progress/:id/:season/:episode",
+            get(video_progress).post(update_video_progress),
+        )
+}
+
+async fn cards(State(library): State<SharedLibrary>) -> impl IntoResponse {
+    let library = library.get();
+    Json(library.cards.iter().map(Card::to_api).collect_vec()).into_response()
+}
+
+async fn card(State(library): State<SharedLibrary>, Path(id): Path<String>) -> impl IntoResponse {
+    let library = library.get();
+    match library.cards.get(&id) {
+        Some(card) => Json(card.to_api()).into_response(),
+        None => error(StatusCode::NOT_FOUND, "No card found with this id"),
+    }
+}
+
+async fn episodes(
+    State(library): State<SharedLibrary>,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    let library = library.get();
+    match library.cards.get(&id) {
+        Some(Card::Show(show)) => Json(show.episodes_api()).into_response(),
+        Some(_) => error(StatusCode::NOT_FOUND, "Card is not a show"),
+        None => error(StatusCode::NOT_FOUND, "No card found with this id"),
+    }
+}
+
+// Watch progress of every video the viewer has started, keyed by '{id}' or '{id}/{season}/{episode}'
+async fn progress(
+    State(store): State<ProgressStore>,
+    Extension(viewer): Extension<Viewer>,
+) -> impl IntoResponse {
+    Json(store.get(&viewer.0)).into_response()
+}
+
+async fn video_progress(
+    State(library): State<SharedLibrary>,
+    State(store): State<ProgressStore>,
+    Extension(viewer): Extension<Viewer>,
+    Path(params): Path<HashMap<String, String>>,
+) -> impl IntoResponse {
+    match video_key(&library, &params) {
+        Ok(key) => Json(store.get(&viewer.0).remove(&key)).into_response(),
+        Err((status, message)) => error(status, message),
+    }
+}
+
+#[derive(Deserialize)]
+struct ProgressUpdate {
+    position: f64,
+    duration: f64,
+    watched: Option<bool>,
+}
+
+async fn update_video_progress(
+    State(library): State<SharedLibrary>,
+    State(store): State<ProgressStore>,
+    Extension(viewer): Extension<Viewer>,
+    Path(params): Path<HashMap<String, String>>,
+    Json(update): Json<ProgressUpdate>,
+) -> impl IntoResponse {
+    let key = match video_key(&library, &params) {
+        Ok(key) => key,
+        Err((status, message)) => return error(status, message),
+    };
+    if !update.position.is_finite() || !update.duration.is_finite() {
+        return error(StatusCode::BAD_REQUEST, "Invalid position or duration");
+    }
+    let result = tokio::task::spawn_blocking(move || {
+        store.update(
+            &viewer.0,
+            &key,
+            update.position,
+            update.duration,
+            update.watched,
+        )
+    })
+    .await;
+    match result {
+        Ok(Ok(progress)) => Json::<Progress>(progress).into_response(),
+        Ok(Err(err)) => {
+            warn!("Failed to save watch progress, '{err}'");
+            error(StatusCode::INTERNAL_SERVER_ERROR, "Failed to save progress")
+        }
+        Err(_) => error(StatusCode::INTERNAL_SERVER_ERROR, "Failed to save progress"),
+    }
+}
+
+// Key of the video in the progress store, only videos of the library are accepted
+fn video_key(
+    library: &SharedLibrary,
+    params: &HashMap<String, String>,
+) -> Result<String, (StatusCode, &'static str)> {
+    let id = params.get("id").map(String::as_str).unwrap_or_default();
+    let episode = match (params.get("season"), params.get("episode")) {
+        (Some(season), Some(episode)) => match (season.parse::<u8>(), episode.parse::<u8>()) {
+            (Ok(season), Ok(episode)) => Some((season, episode)),
+            _ => return Err((StatusCode::BAD_REQUEST, "Invalid season or episode")),
+        },
+        _ => None,
+    };
+    let library = library.get();
+    match library.cards.get(id) {
+        Some(card) if card.has_video(episode) => Ok(match episode {
+            Some((season, episode)) => format!("{id}/{season}/{episode}"),
+            None => id.to_owned(),
+        }),
+        Some(_) => Err((StatusCode::NOT_FOUND, "No such video in this card")),
+        None => Err((StatusCode::NOT_FOUND, "No card found with this id")),
+    }
+}
+
+pub(super) fn error(status: StatusCode, message: &str) -> axum::response::Response {
+    (status, Json(serde_json::json!({ "error": message }))).into_response()
+}
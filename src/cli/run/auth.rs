@@ -0,0 +1,225 @@
+// Imports
+use super::*;
+use crate::auth::{self, Sessions};
+use crate::card::cards::STYLE;
+use axum::{
+    extract::Request,
+    http::{header, HeaderMap, StatusCode},
+    middleware::Next,
+    response::{Redirect, Response},
+    routing::post,
+    Form,
+};
+use serde::Deserialize;
+use tracing::info;
+
+static SESSION_COOKIE: &str = "silvus_session";
+// identifies browsers when authentication is disabled, to keep their watch progress apart
+static CLIENT_COOKIE: &str = "silvus_client";
+
+/// Whose watch progress a request reads and writes, 'user:{name}' or 'client:{token}'
+#[derive(Debug, Clone)]
+pub(super) struct Viewer(pub String);
+
+pub(super) fn router() -> Router<AppState> {
+    Router::new()
+        .route("/login", get(login_page).post(login))
+        .route("/logout", post(logout))
+}
+
+/// Middleware rejecting requests without a valid session, does nothing if no users are configured
+/// besides identifying the client
+pub(super) async fn require(
+    State(sessions): State<Sessions>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    if is_public_asset(request.uri().path()) {
+        return next.run(request).await;
+    }
+
+    if gcfg().users.is_empty() {
+        let client = get_cookie(request.headers(), CLIENT_COOKIE)
+            .filter(|token| token.len() == 64 && token.bytes().all(|byte| byte.is_ascii_hexdigit()))
+            .map(str::to_owned);
+        let is_new = client.is_none();
+        let client = client.unwrap_or_else(auth::random_token);
+        request
+            .extensions_mut()
+            .insert(Viewer(format!("client:{client}")));
+
+        let mut response = next.run(request).await;
+        if is_new {
+            let cookie = format!(
+                "{CLIENT_COOKIE}={client}; Path=/; HttpOnly; SameSite=Lax; Max-Age=31536000{}",
+                secure_attribute()
+            );
+            if let Ok(cookie) = cookie.parse() {
+                response.headers_mut().append(header::SET_COOKIE, cookie);
+            }
+        }
+        return response;
+    }
+
+    let user = get_cookie(request.headers(), SESSION_COOKIE).and_then(|token| sessions.get(token));
+    match user {
+        Some(user) => {
+            request
+                .extensions_mut()
+                .insert(Viewer(format!("user:{user}")));
+            next.run(request).await
+        }
+        None if request.uri().path().starts_with("/api/") => {
+            api::error(StatusCode::UNAUTHORIZED, "Authentication required")
+        }
+        None => Redirect::to("/login").into_response(),
+    }
+}
+
+// The shared assets (stylesheet, icons) are needed by the login page, checked on the decoded
+// path so that something like '/res/.assets/%2e%2e/Movie/movie.mkv' isn't let through
+fn is_public_asset(path: &str) -> bool {
+    let Some(path) = path
+        .strip_prefix("/res/")
+        .and_then(crate::html::decode_path)
+    else {
+        return false;
+    };
+    let segments: Vec<&str> = path.split('/').filter(|seg| !seg.is_empty()).collect();
+    matches!(segments.as_slice(), [".assets", name] if !name.starts_with('.'))
+}
+
+#[derive(Deserialize)]
+struct Credentials {
+    username: String,
+    password: String,
+}
+
+async fn login_page() -> impl IntoResponse {
+    Html(page(None))
+}
+
+async fn login(State(sessions): State<Sessions>, Form(credentials): Form<Credentials>) -> Response {
+    // hashing is expensive, keep it off of the async workers
+    let user = tokio::task::spawn_blocking(move || {
+        auth::authenticate(&gcfg().users, &credentials.username, &credentials.password)
+            .map(str::to_owned)
+    })
+    .await
+    .ok()
+    .flatten();
+
+    let Some(user) = user else {
+        warn!("Failed login attempt");
+        return (
+            StatusCode::UNAUTHORIZED,
+            Html(page(Some("Invalid username or password"))),
+        )
+            .into_response();
+    };
+
+    info!("User '{user}' logged in");
+    let token = sessions.create(&user);
+    (
+        [(
+            header::SET_COOKIE,
+            format!(
+                "{SESSION_COOKIE}={token}; Path=/; HttpOnly; SameSite=Lax; Max-Age={}{}",
+                gcfg().session_lifetime.as_secs(),
+                secure_attribute(),
+            ),
+        )],
+        Redirect::to("/"),
+    )
+        .into_response()
+}
+
+async fn logout(State(sessions): State<Sessions>, headers: HeaderMap) -> Response {
+    if let Some(token) = get_cookie(&headers, SESSION_COOKIE) {
+        sessions.remove(token);
+    }
+    (
+        [(
+            header::SET_COOKIE,
+            format!("{SESSION_COOKIE}=; Path=/; HttpOnly; SameSite=Lax; Max-Age=0"),
+        )],
+        Redirect::to("/login"),
+    )
+        .into_response()
+}
+
+// cookies are only restricted to https when it's enabled
+fn secure_attribute() -> &'static str {
+    if gcfg().tls_cert.is_some() {
+        "; Secure"
+    } else {
+        ""
+    }
+}
+
+fn get_cookie<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers
+        .get_all(header::COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(';'))
+        .find_map(|cookie| {
+            let (key, value) = cookie.trim().split_once('=')?;
+            (key == name).then_some(value)
+        })
+}
+
+fn page(error: Option<&str>) -> String {
+    indoc::formatdoc! {
+        "<!doctype html>
+        <html lang=\"en\">
+            <head>
+                <meta charset=\"UTF-8\" />
+                <meta name=\"viewport\" content=\"width=device-width, initial-scale=1.0\" />
+                <meta name=\"description\" content=\"server\" />
+                <meta name=\"author\" content=\"anesthetice\" />
+                <title>Silvus</title>
+                <style>
+                    {}
+                </style>
+            </head>
+            <body>
+                <div class=\"page-header\">
+                    <img src=\"/res/.assets/icon.svg\" />
+                </div>
+                <form class=\"login\" method=\"post\" action=\"/login\">
+                    {}
+                    <input type=\"text\" name=\"username\" placeholder=\"username\" autocomplete=\"username\" required autofocus />
+                    <input type=\"password\" name=\"password\" placeholder=\"password\" autocomplete=\"current-password\" required />
+                    <button type=\"submit\">log in</button>
+                </form>
+            </body>
+        </html>
+        ",
+        STYLE,
+        match error {
+            Some(error) => format!("<p>{}</p>", crate::html::text(error)),
+            None => String::new(),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn public_assets() {
+        assert!(is_public_asset("/res/.assets/style.css"));
+        assert!(is_public_asset("/res/.assets/default%5Fthumbnail.png"));
+
+        assert!(!is_public_asset("/res/Movie/movie.mkv"));
+        assert!(!is_public_asset("/res/.assets/../Movie/movie.mkv"));
+        assert!(!is_public_asset("/res/.assets/%2e%2e/Movie/movie.mkv"));
+        assert!(!is_public_asset("/res/.assets/%2E%2E%2FMovie%2Fmovie.mkv"));
+        assert!(!is_public_asset("/res/.assets/.."));
+        assert!(!is_public_asset("/res/.assets/"));
+        assert!(!is_public_asset("/res/%2Eassets/x/y"));
+        assert!(!is_public_asset("/api/cards"));
+    }
+}
@@ -0,0 +1,118 @@
+// Imports
+use super::*;
+use crate::card::VIDEO_FILE_EXTENSIONS;
+use crate::utils::{get_extension, get_filestem};
+use axum::{
+    extract::Request,
+    http::StatusCode,
+    middleware::{self, Next},
+    response::Response,
+};
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+use tracing::debug;
+
+/// Serves the files of the library from the target directory, anything else is filtered out
+pub(super) fn router(target_dir: &Path, library: SharedLibrary) -> eyre::Result<Router<AppState>> {
+    let target_dir = Arc::new(
+        target_dir
+            .canonicalize()
+            .wrap_err("Failed to canonicalize target directory")?,
+    );
+    Ok(Router::new()
+        .fallback_service(ServeDir::new(target_dir.as_path()))
+        .layer(middleware::from_fn_with_state(
+            (target_dir, library),
+            filter,
+        )))
+}
+
+async fn filter(
+    State((target_dir, library)): State<(Arc<PathBuf>, SharedLibrary)>,
+    request: Request,
+    next: Next,
+) -> Result<Response, StatusCode> {
+    let Some(path) = crate::html::decode_path(request.uri().path()) else {
+        return Err(StatusCode::NOT_FOUND);
+    };
+    // the content of other cards and thumbnails set through the metadata can be named anything,
+    // they're only served once the library knows them, like subtitles
+    let rel_path = path.trim_start_matches('/');
+    if !is_allowed(&path)
+        && !library
+            .get()
+            .cards
+            .iter()
+            .any(|card| card.has_file(rel_path))
+    {
+        debug!("Refused to serve '{path}'");
+        return Err(StatusCode::NOT_FOUND);
+    }
+    // symlinks are followed by ServeDir, make sure they don't lead out of the target directory
+    if escapes(&target_dir, &path) {
+        warn!("Refused to serve '{path}', resolves outside of the target directory");
+        return Err(StatusCode::NOT_FOUND);
+    }
+    Ok(next.run(request).await)
+}
+
+/// Whether the path, relative to the canonical target directory, exists and resolves outside
+/// of it through a symlink
+pub(super) fn escapes(target_dir: &Path, path: &str) -> bool {
+    target_dir
+        .join(path.trim_start_matches('/'))
+        .canonicalize()
+        .is_ok_and(|fp| !fp.starts_with(target_dir))
+}
+
+// Only allows the shared assets, videos and thumbnails outside of hidden directories
+fn is_allowed(path: &str) -> bool {
+    let segments: Vec<&str> = path.split('/').filter(|seg| !seg.is_empty()).collect();
+    let Some((last, parents)) = segments.split_last() else {
+        return false;
+    };
+    if segments.contains(&"..") {
+        return false;
+    }
+    if parents.first() == Some(&".assets") {
+        return parents.len() == 1 && !last.starts_with('.');
+    }
+    if parents.iter().any(|seg| seg.starts_with('.')) {
+        return false;
+    }
+    let fp = Path::new(last);
+    match last.starts_with('.') {
+        true => get_filestem(fp) == ".thumbnail",
+        false => VIDEO_FILE_EXTENSIONS.contains(&get_extension(fp)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn allowed_paths() {
+        assert!(is_allowed("/Movie/movie.mkv"));
+        assert!(is_allowed("/Show/Season 01/S01E01.mkv"));
+        assert!(is_allowed("/Movie/.thumbnail"));
+        assert!(is_allowed("/Movie/.thumbnail.jpg"));
+        assert!(is_allowed("/.assets/download.svg"));
+
+        assert!(!is_allowed("/"));
+        // served only if they belong to a card
+        assert!(!is_allowed("/Documents/manual.pdf"));
+        assert!(!is_allowed("/Movie/poster.jpg"));
+        assert!(!is_allowed("/Movie/movie.nfo"));
+        assert!(!is_allowed("/Movie/.description"));
+        assert!(!is_allowed("/Movie/.title"));
+        assert!(!is_allowed("/.env"));
+        assert!(!is_allowed("/.git/config"));
+        assert!(!is_allowed("/Movie/.git/HEAD"));
+        assert!(!is_allowed("/.assets/nested/file.svg"));
+        assert!(!is_allowed("/.hidden/.thumbnail"));
+        assert!(!is_allowed("/Movie/../.env"));
+    }
+}
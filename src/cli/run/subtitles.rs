@@ -0,0 +1,85 @@
+// Imports
+use super::*;
+use crate::utils::get_extension;
+use axum::{
+    extract::Path,
+    http::{header, StatusCode},
+};
+
+/// Serves the subtitles of the cards as WebVTT, only files known to the library are read
+pub(super) fn router() -> Router<AppState> {
+    Router::new().route("/*path", get(subtitle))
+}
+
+async fn subtitle(
+    State(library): State<SharedLibrary>,
+    Path(path): Path<String>,
+) -> impl IntoResponse {
+    let library = library.get();
+    if !library.cards.iter().any(|card| card.has_subtitle(&path)) {
+        return StatusCode::NOT_FOUND.into_response();
+    }
+
+    match convert(gcfg().target_dir.as_ref().unwrap(), &path).await {
+        Ok(vtt) => ([(header::CONTENT_TYPE, "text/vtt; charset=utf-8")], vtt).into_response(),
+        Err(status) => status.into_response(),
+    }
+}
+
+// Reads a subtitle file and converts it to WebVTT, symlinks leading out of the target
+// directory are refused like on the /res route
+async fn convert(target_dir: &std::path::Path, path: &str) -> Result<String, StatusCode> {
+    let target_dir = target_dir
+        .canonicalize()
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    if res::escapes(&target_dir, path) {
+        warn!("Refused to serve '{path}', resolves outside of the target directory");
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    let fp = target_dir.join(path);
+    let bytes = match tokio::fs::read(&fp).await {
+        Ok(bytes) => bytes,
+        Err(err) => {
+            warn!("Failed to read '{}', '{err}'", fp.display());
+            return Err(StatusCode::NOT_FOUND);
+        }
+    };
+    // older subtitles aren't always utf-8, unknown characters are replaced
+    let content = String::from_utf8_lossy(&bytes);
+    crate::subtitles::to_vtt(&content, get_extension(&fp)).map_err(|err| {
+        warn!("Failed to convert '{}', '{err}'", fp.display());
+        StatusCode::UNPROCESSABLE_ENTITY
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn escaping_symlink() {
+        let tmp = tempfile::tempdir().unwrap();
+        let target_dir = tmp.path().join("library");
+        std::fs::create_dir_all(target_dir.join("Movie")).unwrap();
+        std::fs::write(
+            target_dir.join("Movie/Movie.fr.srt"),
+            "1\n00:00:01,000 --> 00:00:02,000\nBonjour\n",
+        )
+        .unwrap();
+        std::fs::write(tmp.path().join("id_rsa"), "-----BEGIN KEY-----\n").unwrap();
+        std::os::unix::fs::symlink(
+            tmp.path().join("id_rsa"),
+            target_dir.join("Movie/Movie.en.srt"),
+        )
+        .unwrap();
+
+        let vtt = convert(&target_dir, "Movie/Movie.fr.srt").await.unwrap();
+        assert!(vtt.starts_with("WEBVTT"));
+        assert_eq!(
+            convert(&target_dir, "Movie/Movie.en.srt").await,
+            Err(StatusCode::NOT_FOUND)
+        );
+    }
+}
@@ -0,0 +1,115 @@
+// Imports
+use super::*;
+use axum::{
+    extract::Host,
+    http::{StatusCode, Uri},
+    response::Redirect,
+};
+use axum_server::tls_rustls::RustlsConfig;
+use std::path::PathBuf;
+use tracing::info;
+
+/// Loads the certificate and private key if both are configured
+pub(super) async fn load() -> eyre::Result<Option<RustlsConfig>> {
+    let (cert, key) = match (&gcfg().tls_cert, &gcfg().tls_key) {
+        (Some(cert), Some(key)) => (cert, key),
+        (None, None) => return Ok(None),
+        _ => {
+            return Err(eyre::eyre!(
+                "Both 'tls_cert' and 'tls_key' need to be set to enable TLS"
+            ))
+        }
+    };
+
+    // both ring and aws-lc-rs could end up linked, pick one explicitly
+    let _ = rustls::crypto::ring::default_provider().install_default();
+
+    let config = RustlsConfig::from_pem_file(cert, key)
+        .await
+        .wrap_err_with(|| {
+            format!(
+                "Failed to load TLS certificate '{}' and key '{}'",
+                cert.display(),
+                key.display()
+            )
+        })?;
+    info!("TLS enabled with certificate '{}'", cert.display());
+
+    #[cfg(unix)]
+    tokio::spawn(reload_on_sighup(config.clone(), cert.clone(), key.clone()));
+
+    Ok(Some(config))
+}
+
+// Swaps in the certificate from disk on SIGHUP, existing connections are left untouched
+#[cfg(unix)]
+async fn reload_on_sighup(config: RustlsConfig, cert: PathBuf, key: PathBuf) {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut sighup = match signal(SignalKind::hangup()) {
+        Ok(sighup) => sighup,
+        Err(err) => {
+            warn!("Failed to listen for SIGHUP, certificate reloading is disabled, '{err}'");
+            return;
+        }
+    };
+    while sighup.recv().await.is_some() {
+        match config.reload_from_pem_file(&cert, &key).await {
+            Ok(()) => info!("Reloaded TLS certificate '{}'", cert.display()),
+            Err(err) => {
+                warn!("Failed to reload TLS certificate, keeping the previous one, '{err}'")
+            }
+        }
+    }
+}
+
+/// Plain HTTP application redirecting every request to its HTTPS counterpart
+pub(super) fn redirect_router(https_port: u16) -> Router {
+    Router::new().fallback(move |Host(host): Host, uri: Uri| async move {
+        let host = match host.rsplit_once(':') {
+            // keep IPv6 literals such as '[::1]' intact
+            Some((host, port)) if !port.contains(']') => host.to_owned(),
+            _ => host,
+        };
+        let path = uri.path_and_query().map_or("/", |pq| pq.as_str());
+        match format!("https://{host}:{https_port}{path}").parse::<Uri>() {
+            Ok(uri) => Redirect::permanent(&uri.to_string()).into_response(),
+            Err(_) => StatusCode::BAD_REQUEST.into_response(),
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, http::header, http::Request};
+    use tower::ServiceExt;
+
+    async fn redirect(host: &str, uri: &str) -> String {
+        let request = Request::builder()
+            .uri(uri)
+            .header(header::HOST, host)
+            .body(Body::empty())
+            .unwrap();
+        let response = redirect_router(8443).oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::PERMANENT_REDIRECT);
+        response.headers()[header::LOCATION]
+            .to_str()
+            .unwrap()
+            .to_owned()
+    }
+
+    #[tokio::test]
+    async fn redirects_to_https() {
+        assert_eq!(
+            redirect("example.com:8080", "/watch/1?t=2").await,
+            "https://example.com:8443/watch/1?t=2"
+        );
+        assert_eq!(
+            redirect("example.com", "/").await,
+            "https://example.com:8443/"
+        );
+        assert_eq!(redirect("[::1]:80", "/").await, "https://[::1]:8443/");
+        assert_eq!(redirect("[::1]", "/").await, "https://[::1]:8443/");
+    }
+}
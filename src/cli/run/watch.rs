@@ -0,0 +1,35 @@
+// Imports
+use super::*;
+use axum::{extract::Path, http::StatusCode};
+
+pub(super) fn router() -> Router<AppState> {
+    Router::new()
+        .route("/:id", get(movie_or_show))
+        .route("/:id/:season/:episode", get(episode))
+}
+
+async fn movie_or_show(
+    State(library): State<SharedLibrary>,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    page(&library, &id, None)
+}
+
+async fn episode(
+    State(library): State<SharedLibrary>,
+    Path((id, season, episode)): Path<(String, u8, u8)>,
+) -> impl IntoResponse {
+    page(&library, &id, Some((season, episode)))
+}
+
+fn page(library: &SharedLibrary, id: &str, episode: Option<(u8, u8)>) -> axum::response::Response {
+    let library = library.get();
+    match library
+        .cards
+        .get(id)
+        .and_then(|card| card.to_watch_html_string(episode))
+    {
+        Some(page) => Html(page).into_response(),
+        None => (StatusCode::NOT_FOUND, "Nothing to watch here").into_response(),
+    }
+}
@@ -0,0 +1,25 @@
+use super::*;
+use crate::card::cards::Cards;
+use tracing::info;
+
+pub(super) fn subcommand() -> Command {
+    Command::new("scan")
+        .about("Updates the library index, only the directories that changed are read again")
+        .arg(
+            Arg::new("full")
+                .long("full")
+                .help("Read every directory again, rebuilding the index from scratch (e.g. after replacing videos in place)")
+                .action(ArgAction::SetTrue),
+        )
+}
+
+pub(super) fn process(arg_matches: &ArgMatches) -> eyre::Result<()> {
+    let full = arg_matches.get_flag("full");
+
+    let (cards, rescanned) = Cards::scan(full)?;
+    info!(
+        "Done, {} cards indexed, {rescanned} read from the target directory",
+        cards.iter().count()
+    );
+    Ok(())
+}
@@ -0,0 +1,69 @@
+use super::*;
+use crate::auth::{hash_password, User};
+
+pub(super) fn subcommand() -> Command {
+    let name = Arg::new("name")
+        .index(1)
+        .required(true)
+        .action(ArgAction::Set);
+
+    Command::new("user")
+        .about("Manages the users allowed to access the web interface")
+        .subcommand_required(true)
+        .subcommands([
+            Command::new("add").arg(name.clone()),
+            Command::new("remove").arg(name.clone()),
+            Command::new("passwd").arg(name),
+        ])
+}
+
+pub(super) fn process(arg_matches: &ArgMatches) -> eyre::Result<()> {
+    let (action, arg_matches) = arg_matches
+        .subcommand()
+        .ok_or_eyre("Failed to get user subcommand")?;
+    let name = arg_matches
+        .get_one::<String>("name")
+        .ok_or_eyre("Failed to get name")?;
+
+    let mut owned_config = gcfg().clone();
+    let position = owned_config
+        .users
+        .iter()
+        .position(|user| &user.name == name);
+
+    match (action, position) {
+        ("add", None) => {
+            let password_hash = hash_password(&prompt_password()?)?;
+            owned_config.users.push(User {
+                name: name.to_owned(),
+                password_hash,
+            });
+        }
+        ("add", Some(_)) => return Err(eyre::eyre!("User '{name}' already exists")),
+        ("remove", Some(idx)) => {
+            owned_config.users.remove(idx);
+            if owned_config.users.is_empty() {
+                warn!("No users left, authentication is now disabled");
+            }
+        }
+        ("passwd", Some(idx)) => {
+            owned_config.users[idx].password_hash = hash_password(&prompt_password()?)?;
+        }
+        (_, None) => return Err(eyre::eyre!("User '{name}' does not exist")),
+        _ => return Ok(()),
+    }
+
+    owned_config.save_to_file()?;
+    Ok(())
+}
+
+fn prompt_password() -> eyre::Result<String> {
+    let password = rpassword::prompt_password("Password: ")?;
+    if password.is_empty() {
+        return Err(eyre::eyre!("Password cannot be empty"));
+    }
+    if password != rpassword::prompt_password("Confirm password: ")? {
+        return Err(eyre::eyre!("Passwords do not match"));
+    }
+    Ok(password)
+}
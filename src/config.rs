@@ -1,18 +1,20 @@
 // Imports
-use eyre::Context;
-use serde::{Deserialize, Serialize};
+use eyre::{Context, OptionExt};
+use itertools::Itertools;
+use serde::{Deserialize, Deserializer, Serialize};
 use std::{
-    io::{Read, Write},
-    path::PathBuf,
+    io::Read,
+    net::{IpAddr, Ipv4Addr, SocketAddr},
+    path::{Path, PathBuf},
     sync::OnceLock,
 };
 use tracing::{debug, info, warn};
 
 static CONFIG: OnceLock<Config> = OnceLock::new();
 
-pub fn init() -> eyre::Result<()> {
+pub fn init(config: Config) -> eyre::Result<()> {
     CONFIG
-        .set(Config::load())
+        .set(config)
         .map_err(|_| eyre::eyre!("Failed to set CONFIG"))
 }
 
@@ -21,10 +23,15 @@ pub fn get() -> &'static Config {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Config {
     pub target_dir: Option<PathBuf>,
-    pub port: String,
+    // the application listens on every bind address with this port
+    pub bind: Vec<IpAddr>,
+    #[serde(deserialize_with = "deserialize_port")]
+    pub port: u16,
     pub imdb_url: String,
+    pub imdb_search_url: String,
     pub imdb_description_start_match: String,
     pub imdb_description_end_match: String,
     pub imdb_year_start_match: String,
@@ -33,8 +40,33 @@ pub struct Config {
     pub imdb_image_redirect_end_match: String,
     pub imdb_image_start_match: String,
     pub imdb_image_end_match: String,
+    pub tmdb_api_url: String,
+    pub tmdb_image_url: String,
+    pub tmdb_api_key: Option<String>,
+    pub omdb_api_url: String,
+    pub omdb_api_key: Option<String>,
     pub user_agent: String,
     pub connection_timeout: std::time::Duration,
+    // whole request, from connecting until the end of the response body
+    pub request_timeout: std::time::Duration,
+    // number of cards fetched at once by 'get --all'
+    pub metadata_jobs: usize,
+    // minimum delay between two requests to a metadata provider
+    pub metadata_request_interval: std::time::Duration,
+    // retries of failed metadata requests, the delay doubles after every attempt
+    pub http_retries: u32,
+    pub http_retry_delay: std::time::Duration,
+    // responses older than this are fetched again, zero disables the cache
+    pub http_cache_lifetime: std::time::Duration,
+    pub library_reload_delay: std::time::Duration,
+    // authentication is required as soon as at least one user exists
+    pub users: Vec<crate::auth::User>,
+    pub session_lifetime: std::time::Duration,
+    // PEM encoded, TLS is enabled when both are set
+    pub tls_cert: Option<PathBuf>,
+    pub tls_key: Option<PathBuf>,
+    // plain HTTP port redirecting to HTTPS
+    pub tls_redirect_port: Option<u16>,
 }
 
 #[allow(clippy::derivable_impls)]
@@ -42,8 +74,10 @@ impl Default for Config {
     fn default() -> Self {
         Self {
             target_dir: None,
-            port: "1888".to_string(),
+            bind: vec![IpAddr::V4(Ipv4Addr::UNSPECIFIED)],
+            port: 1888,
             imdb_url: r#"https://www.imdb.com/"#.to_string(),
+            imdb_search_url: r#"https://v3.sg.media-imdb.com/suggestion/x/"#.to_string(),
             imdb_description_start_match: r#"class="sc-2d37a7c7-2 ggeRnl">"#.to_string(),
             imdb_description_end_match: r#"</span></p>"#.to_string(),
             imdb_year_start_match: r#"releaseinfo?ref_=tt_ov_rdat">"#.to_string(),
@@ -53,9 +87,26 @@ impl Default for Config {
             imdb_image_redirect_end_match: r#"""#.to_string(),
             imdb_image_start_match: r#"https://m.media-amazon.com/images/"#.to_string(),
             imdb_image_end_match: r#"""#.to_string(),
+            tmdb_api_url: r#"https://api.themoviedb.org/3"#.to_string(),
+            tmdb_image_url: r#"https://image.tmdb.org/t/p/original"#.to_string(),
+            tmdb_api_key: None,
+            omdb_api_url: r#"https://www.omdbapi.com/"#.to_string(),
+            omdb_api_key: None,
             user_agent: "Mozilla/5.0 (X11; Linux x86_64; rv:129.0) Gecko/20100101 Firefox/129.0"
                 .to_string(),
             connection_timeout: std::time::Duration::from_secs(5),
+            request_timeout: std::time::Duration::from_secs(30),
+            metadata_jobs: 4,
+            metadata_request_interval: std::time::Duration::from_millis(250),
+            http_retries: 3,
+            http_retry_delay: std::time::Duration::from_secs(1),
+            http_cache_lifetime: std::time::Duration::from_secs(7 * 24 * 60 * 60),
+            library_reload_delay: std::time::Duration::from_secs(2),
+            users: Vec::new(),
+            session_lifetime: std::time::Duration::from_secs(7 * 24 * 60 * 60),
+            tls_cert: None,
+            tls_key: None,
+            tls_redirect_port: None,
         }
     }
 }
@@ -65,7 +116,8 @@ impl Config {
 
     pub fn load() -> Self {
         debug!("Attempting to load config...");
-        match Self::load_from_file() {
+        let filepath = crate::dirs::get().config_dir().join(Self::FILENAME);
+        match Self::load_from_file(&filepath) {
             Ok(config) => {
                 debug!("Config successfully loaded from file");
                 config
@@ -90,13 +142,12 @@ impl Config {
         }
     }
 
-    fn load_from_file() -> eyre::Result<Self> {
-        let filepath = crate::dirs::get().config_dir().join(Self::FILENAME);
+    pub fn load_from_file(filepath: &Path) -> eyre::Result<Self> {
         let mut bytes: Vec<u8> = Vec::new();
 
         let mut read_file = std::fs::OpenOptions::new()
             .read(true)
-            .open(&filepath)
+            .open(filepath)
             .wrap_err_with(|| {
                 format!(
                     "Failed to read/open config file with path '{}'",
@@ -113,22 +164,112 @@ impl Config {
         let filepath = crate::dirs::get().config_dir().join(Self::FILENAME);
         let bytes = serde_json::to_vec_pretty(&ijson::to_value(self)?)?;
 
-        let mut write_file = std::fs::OpenOptions::new()
-            .write(true)
-            .truncate(true)
-            .create(true)
-            .open(&filepath)
-            .wrap_err_with(|| {
-                format!(
-                    "Failed to write/truncate/create/open config file with path '{}'",
-                    filepath.display()
-                )
-            })?;
-        write_file.write_all(&bytes)?;
-        write_file.sync_all()?;
+        crate::utils::write_atomic(&filepath, &bytes).wrap_err_with(|| {
+            format!(
+                "Failed to write config file with path '{}'",
+                filepath.display()
+            )
+        })?;
 
         debug!("Saved config to '{}'", filepath.display());
 
         Ok(())
     }
+
+    pub fn listen_addresses(&self) -> Vec<SocketAddr> {
+        self.bind
+            .iter()
+            .map(|ip| SocketAddr::new(*ip, self.port))
+            .collect()
+    }
+
+    /// Checks the settings required by the run subcommand
+    pub fn validate(&self) -> eyre::Result<()> {
+        let target_dir = self
+            .target_dir
+            .as_ref()
+            .ok_or_eyre("No target path set, use the init subcommand")?;
+        if !target_dir.is_dir() {
+            return Err(eyre::eyre!(
+                "Target directory '{}' does not exist",
+                target_dir.display()
+            ));
+        }
+        if self.port == 0 {
+            return Err(eyre::eyre!("Invalid port, must be between 1 and 65535"));
+        }
+        if self.bind.is_empty() {
+            return Err(eyre::eyre!("No bind address set"));
+        }
+        if let Some(ip) = self.bind.iter().duplicates().next() {
+            return Err(eyre::eyre!("Bind address '{ip}' is set more than once"));
+        }
+        if self.tls_redirect_port == Some(self.port) {
+            return Err(eyre::eyre!(
+                "'tls_redirect_port' must differ from the application port"
+            ));
+        }
+        Ok(())
+    }
+}
+
+// older configs stored the port as a string
+fn deserialize_port<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u16, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Port {
+        Number(u16),
+        String(String),
+    }
+    match Port::deserialize(deserializer)? {
+        Port::Number(port) => Ok(port),
+        Port::String(port) => port.trim().parse().map_err(serde::de::Error::custom),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validate() {
+        let dir = tempfile::tempdir().unwrap();
+        let valid = Config {
+            target_dir: Some(dir.path().to_path_buf()),
+            ..Config::default()
+        };
+        assert!(valid.validate().is_ok());
+
+        for invalid in [
+            Config::default(),
+            Config {
+                target_dir: Some(dir.path().join("missing")),
+                ..valid.clone()
+            },
+            Config {
+                port: 0,
+                ..valid.clone()
+            },
+            Config {
+                bind: Vec::new(),
+                ..valid.clone()
+            },
+            Config {
+                bind: vec![IpAddr::V4(Ipv4Addr::LOCALHOST); 2],
+                ..valid.clone()
+            },
+            Config {
+                tls_redirect_port: Some(valid.port),
+                ..valid.clone()
+            },
+        ] {
+            assert!(invalid.validate().is_err());
+        }
+    }
+
+    #[test]
+    fn legacy_string_port() {
+        let config: Config = serde_json::from_str(r#"{ "port": " 8080 " }"#).unwrap();
+        assert_eq!(config.port, 8080);
+    }
 }
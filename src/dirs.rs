@@ -15,3 +15,8 @@ pub fn init() -> eyre::Result<()> {
 pub fn get() -> &'static ProjectDirs {
     DIRS.get().unwrap()
 }
+
+/// Same as get, None before the directories are initialized
+pub fn try_get() -> Option<&'static ProjectDirs> {
+    DIRS.get()
+}
@@ -0,0 +1,164 @@
+// Escaping of user-controlled strings (titles, descriptions, filenames, ...) before
+// they are interpolated into the generated markup
+
+/// Escapes a string to be used as html text content
+pub fn text(input: &str) -> String {
+    let mut output = String::with_capacity(input.len());
+    for chr in input.chars() {
+        match chr {
+            '&' => output.push_str("&amp;"),
+            '<' => output.push_str("&lt;"),
+            '>' => output.push_str("&gt;"),
+            _ => output.push(chr),
+        }
+    }
+    output
+}
+
+/// Escapes a string to be used inside of a quoted html attribute value
+pub fn attr(input: &str) -> String {
+    let mut output = String::with_capacity(input.len());
+    for chr in input.chars() {
+        match chr {
+            '&' => output.push_str("&amp;"),
+            '<' => output.push_str("&lt;"),
+            '>' => output.push_str("&gt;"),
+            '"' => output.push_str("&quot;"),
+            '\'' => output.push_str("&#39;"),
+            _ => output.push(chr),
+        }
+    }
+    output
+}
+
+/// Decodes the html entities commonly found in scraped text, unknown entities are kept as is
+pub fn unescape(input: &str) -> String {
+    let mut output = String::with_capacity(input.len());
+    let mut rest = input;
+    while let Some(idx) = rest.find('&') {
+        output.push_str(&rest[..idx]);
+        rest = &rest[idx..];
+        let decoded = rest.find(';').filter(|end| *end <= 10).and_then(|end| {
+            let chr = match &rest[1..end] {
+                "amp" => '&',
+                "lt" => '<',
+                "gt" => '>',
+                "quot" => '"',
+                "apos" => '\'',
+                "nbsp" => '\u{a0}',
+                entity => {
+                    let code = match entity.strip_prefix("#x").or(entity.strip_prefix("#X")) {
+                        Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                        None => entity.strip_prefix('#')?.parse::<u32>().ok()?,
+                    };
+                    char::from_u32(code)?
+                }
+            };
+            Some((chr, end))
+        });
+        match decoded {
+            Some((chr, end)) => {
+                output.push(chr);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                output.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    output.push_str(rest);
+    output
+}
+
+/// Percent-encodes every segment of a relative path, keeping the '/' separators
+pub fn encode_path(path: &str) -> String {
+    let mut output = String::with_capacity(path.len());
+    for byte in path.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' => {
+                output.push(char::from(byte))
+            }
+            _ => output.push_str(&format!("%{byte:02X}")),
+        }
+    }
+    output
+}
+
+/// Decodes a percent-encoded path, None if it isn't valid utf-8 once decoded
+pub fn decode_path(path: &str) -> Option<String> {
+    let bytes = path.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut idx = 0;
+    while idx < bytes.len() {
+        if bytes[idx] == b'%' {
+            let hex = std::str::from_utf8(bytes.get(idx + 1..idx + 3)?).ok()?;
+            decoded.push(u8::from_str_radix(hex, 16).ok()?);
+            idx += 3;
+        } else {
+            decoded.push(bytes[idx]);
+            idx += 1;
+        }
+    }
+    String::from_utf8(decoded).ok()
+}
+
+/// Url of a file served by the /res route, from its path relative to the target directory
+pub fn res_url(path: &str) -> String {
+    format!("/res/{}", encode_path(path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escape_text() {
+        assert_eq!(
+            text("<script>alert(1)</script>"),
+            "&lt;script&gt;alert(1)&lt;/script&gt;"
+        );
+        assert_eq!(text("Tom & Jerry"), "Tom &amp; Jerry");
+        assert_eq!(text(r#"the "best" one's"#), r#"the "best" one's"#);
+    }
+
+    #[test]
+    fn escape_attr() {
+        assert_eq!(
+            attr(r#"" onerror="alert(1)"#),
+            "&quot; onerror=&quot;alert(1)"
+        );
+        assert_eq!(attr("a'b&c<d>"), "a&#39;b&amp;c&lt;d&gt;");
+    }
+
+    #[test]
+    fn unescape_entities() {
+        assert_eq!(
+            unescape("Laura Palmer&apos;s secrets &amp; lies"),
+            "Laura Palmer's secrets & lies"
+        );
+        assert_eq!(unescape("&#x2019;Brazil&#8217;"), "\u{2019}Brazil\u{2019}");
+        assert_eq!(unescape("AT&T &unknown; &"), "AT&T &unknown; &");
+    }
+
+    #[test]
+    fn encode_res_urls() {
+        assert_eq!(res_url("Movie/movie.mkv"), "/res/Movie/movie.mkv");
+        assert_eq!(
+            res_url("My Show/Season 01/S01E01 #1?.mkv"),
+            "/res/My%20Show/Season%2001/S01E01%20%231%3F.mkv"
+        );
+        assert_eq!(res_url("Amélie/a&b.mp4"), "/res/Am%C3%A9lie/a%26b.mp4");
+        assert_eq!(res_url(r#"x"y.mp4"#), "/res/x%22y.mp4");
+    }
+
+    #[test]
+    fn decoded_paths() {
+        assert_eq!(
+            decode_path("/My%20Show/%2Edescription").as_deref(),
+            Some("/My Show/.description")
+        );
+        assert_eq!(decode_path("/bad%2").as_deref(), None);
+        assert_eq!(decode_path("/bad%ZZ").as_deref(), None);
+    }
+}
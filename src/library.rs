@@ -0,0 +1,87 @@
+// Imports
+use crate::card::cards::Cards;
+use eyre::OptionExt;
+use notify_debouncer_full::{
+    new_debouncer,
+    notify::{EventKind, RecommendedWatcher, RecursiveMode},
+    DebounceEventResult, Debouncer, RecommendedCache,
+};
+use std::sync::{Arc, RwLock};
+use tracing::{debug, info, warn};
+
+pub struct Library {
+    pub cards: Cards,
+    pub page: String,
+}
+
+impl Library {
+    pub fn load() -> eyre::Result<Self> {
+        let cards = Cards::load()?;
+        let page = cards.generate_static_html_page();
+        Ok(Self { cards, page })
+    }
+}
+
+// The library currently being served, swapped out as a whole on reload so that
+// requests never observe a partially rebuilt library
+#[derive(Clone)]
+pub struct SharedLibrary(Arc<RwLock<Arc<Library>>>);
+
+impl SharedLibrary {
+    pub fn new(library: Library) -> Self {
+        Self(Arc::new(RwLock::new(Arc::new(library))))
+    }
+
+    pub fn get(&self) -> Arc<Library> {
+        // a poisoned lock still holds a complete library
+        Arc::clone(&self.0.read().unwrap_or_else(|err| err.into_inner()))
+    }
+
+    pub fn reload(&self) -> eyre::Result<()> {
+        let library = Arc::new(Library::load()?);
+        *self.0.write().unwrap_or_else(|err| err.into_inner()) = library;
+        Ok(())
+    }
+}
+
+pub type Watcher = Debouncer<RecommendedWatcher, RecommendedCache>;
+
+/// Reloads the library whenever something changes inside of the target directory,
+/// the returned watcher stops watching once dropped
+pub fn watch(library: SharedLibrary) -> eyre::Result<Watcher> {
+    let path = crate::config::get()
+        .target_dir
+        .to_owned()
+        .ok_or_eyre("No target path set, use the init subcommand")?;
+
+    let mut debouncer = new_debouncer(
+        crate::config::get().library_reload_delay,
+        None,
+        move |result: DebounceEventResult| match result {
+            Ok(events) => {
+                // reloading the library reads every card, ignoring access events avoids a loop
+                let count = events
+                    .iter()
+                    .filter(|event| !matches!(event.kind, EventKind::Access(_)))
+                    .count();
+                if count == 0 {
+                    return;
+                }
+                debug!("Detected {count} change(s) in the target directory");
+                match library.reload() {
+                    Ok(()) => info!("Library reloaded"),
+                    Err(err) => warn!("Failed to reload library, '{err}'"),
+                }
+            }
+            Err(errs) => {
+                for err in errs {
+                    warn!("Failed to watch target directory, '{err}'");
+                }
+            }
+        },
+    )?;
+    debouncer.watch(&path, RecursiveMode::Recursive)?;
+    info!("Watching '{}' for changes", path.display());
+
+    Ok(debouncer)
+}
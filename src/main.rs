@@ -1,8 +1,15 @@
 // Modules
+mod auth;
 mod card;
 mod cli;
 mod config;
 mod dirs;
+mod html;
+mod library;
+mod metadata;
+mod probe;
+mod progress;
+mod subtitles;
 mod utils;
 
 // Imports
@@ -79,10 +86,7 @@ fn main() -> eyre::Result<()> {
     tracing::subscriber::set_global_default(subscriber).expect("Failed to set global subscriber");
     info!("{}", utils::datetime_to_pretty_string(&dt));
 
-    // 5. init config
-    config::init()?;
-
-    // ## CLI
+    // ## CLI, also initializes the config as it can be overridden by arguments
     cli::cli()?;
 
     Ok(())
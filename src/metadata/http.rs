@@ -0,0 +1,270 @@
+// Imports
+use crate::config::get as gcfg;
+use eyre::OptionExt;
+use reqwest::{header::RETRY_AFTER, Client, Request, Response, StatusCode};
+use std::{
+    path::PathBuf,
+    time::{Duration, SystemTime},
+};
+use tracing::{debug, warn};
+
+// upper bound of a single wait between two attempts, whatever the server asks for
+static MAX_RETRY_DELAY: Duration = Duration::from_secs(60);
+
+/// Client used for every metadata request, retries transient failures and caches
+/// successful responses on disk
+#[derive(Debug, Clone)]
+pub struct HttpClient {
+    client: Client,
+    retries: u32,
+    // doubled after every failed attempt
+    retry_delay: Duration,
+    cache: Option<Cache>,
+}
+
+#[derive(Debug, Clone)]
+struct Cache {
+    dir: PathBuf,
+    lifetime: Duration,
+}
+
+impl HttpClient {
+    /// Client without cache, with the default retry settings
+    pub fn new(client: Client) -> Self {
+        Self {
+            client,
+            retries: 3,
+            retry_delay: Duration::from_millis(500),
+            cache: None,
+        }
+    }
+
+    pub fn from_config(use_cache: bool) -> eyre::Result<Self> {
+        let client = Client::builder()
+            .connection_verbose(true)
+            .connect_timeout(gcfg().connection_timeout)
+            .timeout(gcfg().request_timeout)
+            .user_agent(&gcfg().user_agent)
+            .build()?;
+        let lifetime = gcfg().http_cache_lifetime;
+        Ok(Self {
+            retries: gcfg().http_retries,
+            retry_delay: gcfg().http_retry_delay,
+            cache: (use_cache && !lifetime.is_zero()).then(|| Cache {
+                dir: crate::dirs::get().data_local_dir().join("http_cache"),
+                lifetime,
+            }),
+            ..Self::new(client)
+        })
+    }
+
+    /// Sends a GET request and returns the body, served from the cache if a fresh copy exists
+    pub async fn get(&self, url: &str, query: &[(&str, &str)]) -> eyre::Result<Vec<u8>> {
+        self.get_with(url, query, |_| true).await
+    }
+
+    /// Same as get, the body is only cached if it passes the check, so that error payloads
+    /// sent with a 200 status are fetched again next time
+    pub async fn get_with(
+        &self,
+        url: &str,
+        query: &[(&str, &str)],
+        cacheable: impl FnOnce(&[u8]) -> bool,
+    ) -> eyre::Result<Vec<u8>> {
+        let request = self
+            .client
+            .get(url)
+            .query(query)
+            .build()
+            .map_err(|err| err.without_url())?;
+
+        // the url may contain an api key, only its hash ends up on disk
+        let key = format!(
+            "{:016x}",
+            crate::utils::fnv1a(request.url().as_str().as_bytes())
+        );
+        if let Some(body) = self.cache.as_ref().and_then(|cache| cache.read(&key)) {
+            debug!("Cache hit for '{key}'");
+            return Ok(body);
+        }
+
+        let body = self.send(request).await?;
+        if let Some(cache) = self.cache.as_ref().filter(|_| cacheable(&body)) {
+            if let Err(err) = cache.write(&key, &body) {
+                warn!("Failed to cache response, '{err}'");
+            }
+        }
+        Ok(body)
+    }
+
+    pub async fn get_text(&self, url: &str, query: &[(&str, &str)]) -> eyre::Result<String> {
+        Ok(String::from_utf8_lossy(&self.get(url, query).await?).into_owned())
+    }
+
+    // Retries on timeouts, connection errors, 5xx and 429 responses with an exponential backoff
+    async fn send(&self, request: Request) -> eyre::Result<Vec<u8>> {
+        let mut attempt = 0;
+        loop {
+            let retry = attempt < self.retries;
+            let request = request.try_clone().ok_or_eyre("Failed to clone request")?;
+            let retry_after = match self.client.execute(request).await {
+                Ok(response) if response.status().is_success() => match response.bytes().await {
+                    Ok(bytes) => return Ok(bytes.to_vec()),
+                    Err(err) if retry && is_transient(&err) => None,
+                    Err(err) => return Err(err.without_url().into()),
+                },
+                Ok(response) if retry && is_retryable(response.status()) => retry_after(&response),
+                Ok(response) => return Err(eyre::eyre!("HTTP status {}", response.status())),
+                Err(err) if retry && is_transient(&err) => None,
+                Err(err) => return Err(err.without_url().into()),
+            };
+
+            let delay = retry_after
+                .unwrap_or(
+                    self.retry_delay
+                        .saturating_mul(2_u32.saturating_pow(attempt)),
+                )
+                .min(MAX_RETRY_DELAY);
+            attempt += 1;
+            warn!(
+                "Request failed, retrying in {}ms ({attempt}/{})",
+                delay.as_millis(),
+                self.retries
+            );
+            tokio::time::sleep(delay).await;
+        }
+    }
+}
+
+impl Cache {
+    fn read(&self, key: &str) -> Option<Vec<u8>> {
+        let path = self.dir.join(key);
+        let age = std::fs::metadata(&path)
+            .and_then(|metadata| metadata.modified())
+            .ok()
+            .and_then(|modified| SystemTime::now().duration_since(modified).ok())?;
+        if age > self.lifetime {
+            return None;
+        }
+        std::fs::read(path).ok()
+    }
+
+    fn write(&self, key: &str, body: &[u8]) -> eyre::Result<()> {
+        std::fs::create_dir_all(&self.dir)?;
+        crate::utils::write_atomic(&self.dir.join(key), body)?;
+        Ok(())
+    }
+}
+
+fn is_transient(err: &reqwest::Error) -> bool {
+    err.is_timeout() || err.is_connect()
+}
+
+fn is_retryable(status: StatusCode) -> bool {
+    status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS
+}
+
+// Only the delay-seconds form is supported, http dates fall back to the backoff
+fn retry_after(response: &Response) -> Option<Duration> {
+    response
+        .headers()
+        .get(RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse::<u64>()
+        .ok()
+        .map(Duration::from_secs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{http::HeaderMap, routing::get};
+    use std::sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc,
+    };
+
+    // Answers with the given statuses in order, then with 200 and the number of calls
+    async fn mock(statuses: Vec<StatusCode>) -> (String, Arc<AtomicU32>) {
+        let calls = Arc::new(AtomicU32::new(0));
+        let counter = calls.clone();
+        let router = axum::Router::new().route(
+            "/",
+            get(move || async move {
+                let call = counter.fetch_add(1, Ordering::SeqCst);
+                let mut headers = HeaderMap::new();
+                headers.insert(RETRY_AFTER, "0".parse().unwrap());
+                let status = statuses
+                    .get(call as usize)
+                    .copied()
+                    .unwrap_or(StatusCode::OK);
+                (status, headers, format!("call {}", call + 1))
+            }),
+        );
+        (crate::metadata::tests::serve(router).await, calls)
+    }
+
+    fn client() -> HttpClient {
+        HttpClient {
+            retry_delay: Duration::from_millis(1),
+            ..HttpClient::new(Client::new())
+        }
+    }
+
+    #[tokio::test]
+    async fn retries() {
+        let (url, calls) = mock(vec![
+            StatusCode::SERVICE_UNAVAILABLE,
+            StatusCode::TOO_MANY_REQUESTS,
+        ])
+        .await;
+        assert_eq!(client().get_text(&url, &[]).await.unwrap(), "call 3");
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+
+        // client errors aren't retried
+        let (url, calls) = mock(vec![StatusCode::NOT_FOUND]).await;
+        let err = client().get_text(&url, &[]).await.unwrap_err();
+        assert_eq!(err.to_string(), "HTTP status 404 Not Found");
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        // gives up after the last retry
+        let (url, calls) = mock(vec![StatusCode::BAD_GATEWAY; 5]).await;
+        assert!(client().get_text(&url, &[]).await.is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 4);
+    }
+
+    #[tokio::test]
+    async fn cache() {
+        let dir = tempfile::tempdir().unwrap();
+        let client = HttpClient {
+            cache: Some(Cache {
+                dir: dir.path().to_path_buf(),
+                lifetime: Duration::from_secs(60),
+            }),
+            ..client()
+        };
+        let (url, calls) = mock(Vec::new()).await;
+
+        assert_eq!(
+            client.get_text(&url, &[("q", "a")]).await.unwrap(),
+            "call 1"
+        );
+        assert_eq!(
+            client.get_text(&url, &[("q", "a")]).await.unwrap(),
+            "call 1"
+        );
+        assert_eq!(
+            client.get_text(&url, &[("q", "b")]).await.unwrap(),
+            "call 2"
+        );
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+
+        // rejected bodies aren't cached
+        for call in ["call 3", "call 4"] {
+            let body = client.get_with(&url, &[("q", "c")], |_| false).await;
+            assert_eq!(body.unwrap(), call.as_bytes());
+        }
+    }
+}
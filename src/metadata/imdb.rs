@@ -0,0 +1,322 @@
+use super::*;
+use crate::config::get as gcfg;
+use crate::html;
+use serde_json::Value;
+use tracing::debug;
+
+#[derive(Debug)]
+pub struct Imdb {
+    url: String,
+    search_url: String,
+}
+
+impl Imdb {
+    pub fn from_config() -> Self {
+        Self {
+            url: gcfg().imdb_url.clone(),
+            search_url: gcfg().imdb_search_url.clone(),
+        }
+    }
+}
+
+impl MetadataProvider for Imdb {
+    /// Scrapes the metadata from an IMDb title page
+    async fn fetch(&self, client: &HttpClient, link: &str) -> eyre::Result<Metadata> {
+        let base = self.url.trim_end_matches('/');
+        let link = match link.starts_with("http") {
+            true => link.to_owned(),
+            false => {
+                let id = imdb_id(link).ok_or_eyre("Invalid IMDb link or id")?;
+                format!("{base}/title/{id}/")
+            }
+        };
+        let text = client.get_text(&link, &[]).await?;
+        let mut metadata = parse(&text);
+
+        if metadata.image_url.is_none() {
+            // without structured data the poster is only reachable through the media viewer
+            let image_redirect = find_between(
+                &text,
+                &gcfg().imdb_image_redirect_start_match,
+                &gcfg().imdb_image_redirect_end_match,
+            )
+            .ok_or_eyre("Failed to find a match for the image redirect")?;
+            let image_redirect = base.to_owned() + image_redirect;
+
+            let text = client.get_text(&image_redirect, &[]).await?;
+            let sidx = text
+                .find(&gcfg().imdb_image_start_match)
+                .ok_or_eyre("Failed to find a match for the start of the image")?;
+            let offset = text[sidx..]
+                .find(&gcfg().imdb_image_end_match)
+                .ok_or_eyre("Failed to find a match for the end of the image")?;
+            metadata.image_url = Some(text[sidx..sidx + offset].to_owned());
+        }
+
+        Ok(metadata)
+    }
+
+    /// Uses the suggestion api of the IMDb search bar
+    async fn search(
+        &self,
+        client: &HttpClient,
+        query: &str,
+        _year: Option<&str>,
+    ) -> eyre::Result<Vec<Candidate>> {
+        let url = format!(
+            "{}/{}.json",
+            self.search_url.trim_end_matches('/'),
+            html::encode_path(&query.to_lowercase().replace('/', " "))
+        );
+        let value = get_json(client, &url, &[]).await?;
+
+        Ok(value
+            .get("d")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(|entry| {
+                // suggestions also contain people, their ids start with 'nm'
+                let id = entry
+                    .get("id")?
+                    .as_str()
+                    .filter(|id| id.starts_with("tt"))?;
+                Some(Candidate {
+                    title: entry.get("l")?.as_str()?.to_owned(),
+                    year: entry
+                        .get("y")
+                        .and_then(Value::as_u64)
+                        .map(|y| y.to_string()),
+                    kind: entry.get("q").and_then(Value::as_str).map(str::to_owned),
+                    link: format!("{}/title/{id}/", self.url.trim_end_matches('/')),
+                    score: 0.0,
+                })
+            })
+            .collect())
+    }
+}
+
+/// Extracts the metadata from the JSON-LD block of the page, falling back to the configured matchers
+pub fn parse(text: &str) -> Metadata {
+    let mut metadata = parse_json_ld(text).unwrap_or_else(|| {
+        debug!("No usable JSON-LD block found, falling back to matchers");
+        Metadata::default()
+    });
+
+    if metadata.description.is_none() {
+        metadata.description = find_between(
+            text,
+            &gcfg().imdb_description_start_match,
+            &gcfg().imdb_description_end_match,
+        )
+        .map(html::unescape);
+    }
+    if metadata.year.is_none() {
+        metadata.year = find_between(
+            text,
+            &gcfg().imdb_year_start_match,
+            &gcfg().imdb_year_end_match,
+        )
+        .map(html::unescape);
+    }
+
+    metadata
+}
+
+fn parse_json_ld(text: &str) -> Option<Metadata> {
+    let mut rest = text;
+    while let Some(idx) = rest.find("application/ld+json") {
+        rest = &rest[idx..];
+        let Some(block) = find_between(rest, ">", "</script>") else {
+            break;
+        };
+        rest = &rest[1..];
+
+        let value: Value = match serde_json::from_str(block) {
+            Ok(value) => value,
+            Err(err) => {
+                debug!("Failed to parse JSON-LD block, '{err}'");
+                continue;
+            }
+        };
+        if value.get("name").is_none() {
+            continue;
+        }
+
+        let string = |key: &str| {
+            value
+                .get(key)
+                .and_then(Value::as_str)
+                .map(|string| html::unescape(string).trim().to_owned())
+                .filter(|string| !string.is_empty())
+        };
+
+        return Some(Metadata {
+            title: string("name"),
+            year: string("datePublished").and_then(|date| date.get(..4).map(str::to_owned)),
+            description: string("description"),
+            image_url: match value.get("image") {
+                Some(Value::String(url)) => Some(url.to_owned()),
+                Some(Value::Object(image)) => image
+                    .get("url")
+                    .or(image.get("contentUrl"))
+                    .and_then(Value::as_str)
+                    .map(str::to_owned),
+                _ => None,
+            },
+            genres: match value.get("genre") {
+                Some(Value::String(genre)) => vec![html::unescape(genre)],
+                Some(Value::Array(genres)) => genres
+                    .iter()
+                    .filter_map(Value::as_str)
+                    .map(html::unescape)
+                    .collect(),
+                _ => Vec::new(),
+            },
+            rating: value
+                .pointer("/aggregateRating/ratingValue")
+                .and_then(|rating| match rating {
+                    Value::String(rating) => rating.parse::<f32>().ok(),
+                    rating => rating.as_f64().map(|rating| rating as f32),
+                }),
+            runtime: string("duration").as_deref().and_then(parse_duration),
+            episodes: Vec::new(),
+        });
+    }
+    None
+}
+
+// returns the text between the first occurrence of start and the next occurrence of end
+fn find_between<'a>(text: &'a str, start: &str, end: &str) -> Option<&'a str> {
+    let sidx = text.find(start)? + start.len();
+    let offset = text[sidx..].find(end)?;
+    Some(&text[sidx..sidx + offset])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use reqwest::Client;
+
+    fn init() {
+        let _ = crate::config::init(Config::default());
+    }
+
+    #[test]
+    fn movie_json_ld() {
+        init();
+        let metadata = parse(include_str!("../../tests/fixtures/imdb_movie.html"));
+        assert_eq!(metadata.title.as_deref(), Some("The Shawshank Redemption"));
+        assert_eq!(metadata.year.as_deref(), Some("1994"));
+        assert!(metadata
+            .description
+            .unwrap()
+            .starts_with("A banker convicted of uxoricide"));
+        assert_eq!(
+            metadata.image_url.as_deref(),
+            Some("https://m.media-amazon.com/images/M/MV5BMDAyY2FhYjctNDc5OS00MDNlLThiMGUtY2UxYWVkNGY2ZjljXkEyXkFqcGc@._V1_.jpg")
+        );
+        assert_eq!(metadata.genres, vec!["Drama"]);
+        assert_eq!(metadata.rating, Some(9.3));
+        assert_eq!(metadata.runtime, Some(142));
+    }
+
+    #[test]
+    fn series_json_ld() {
+        init();
+        let metadata = parse(include_str!("../../tests/fixtures/imdb_series.html"));
+        assert_eq!(metadata.title.as_deref(), Some("Twin Peaks"));
+        assert_eq!(metadata.year.as_deref(), Some("1990"));
+        assert!(metadata
+            .description
+            .unwrap()
+            .ends_with("Laura Palmer's secrets & lies unravel."));
+        assert_eq!(metadata.genres, vec!["Crime"]);
+        assert_eq!(metadata.runtime, Some(47));
+    }
+
+    #[test]
+    fn matcher_fallback() {
+        init();
+        let metadata = parse(include_str!("../../tests/fixtures/imdb_legacy.html"));
+        assert_eq!(metadata.title, None);
+        assert_eq!(metadata.year.as_deref(), Some("1985"));
+        assert_eq!(
+            metadata.description.as_deref(),
+            Some("A bureaucrat in a dystopic society becomes an enemy of the state as he pursues the woman of his dreams.")
+        );
+        assert_eq!(metadata.image_url, None);
+    }
+
+    #[tokio::test]
+    async fn fetch_with_image_redirect() {
+        use axum::{response::Html, routing::get};
+
+        init();
+        let url = crate::metadata::tests::serve(
+            axum::Router::new()
+                .route(
+                    "/title/tt0088846/",
+                    get(|| async { Html(include_str!("../../tests/fixtures/imdb_legacy.html")) }),
+                )
+                .route(
+                    "/title/tt0088846/mediaviewer/rm3587366912/",
+                    get(|| async {
+                        Html(r#"<img src="https://m.media-amazon.com/images/M/brazil.jpg"/>"#)
+                    }),
+                ),
+        )
+        .await;
+
+        let metadata = Imdb {
+            url,
+            search_url: String::new(),
+        }
+        .fetch(&HttpClient::new(Client::new()), "tt0088846")
+        .await
+        .unwrap();
+        assert_eq!(metadata.year.as_deref(), Some("1985"));
+        assert_eq!(
+            metadata.image_url.as_deref(),
+            Some("https://m.media-amazon.com/images/M/brazil.jpg")
+        );
+    }
+
+    #[tokio::test]
+    async fn search_suggestions() {
+        use axum::{routing::get, Json};
+
+        let search_url = crate::metadata::tests::serve(axum::Router::new().route(
+            "/blade%20runner.json",
+            get(|| async {
+                Json(serde_json::json!({ "d": [
+                    { "id": "tt0083658", "l": "Blade Runner", "q": "feature", "y": 1982 },
+                    { "id": "nm0000549", "l": "Ridley Scott", "s": "Director" },
+                    { "id": "tt1856101", "l": "Blade Runner 2049", "q": "feature", "y": 2017 }
+                ]}))
+            }),
+        ))
+        .await;
+
+        let candidates = Imdb {
+            url: "https://www.imdb.com/".to_string(),
+            search_url,
+        }
+        .search(&HttpClient::new(Client::new()), "Blade Runner", None)
+        .await
+        .unwrap();
+        assert_eq!(candidates.len(), 2);
+        assert_eq!(candidates[0].to_string(), "Blade Runner (1982) • feature");
+        assert_eq!(candidates[0].link, "https://www.imdb.com/title/tt0083658/");
+    }
+
+    #[test]
+    fn durations() {
+        assert_eq!(parse_duration("PT2H22M"), Some(142));
+        assert_eq!(parse_duration("PT47M"), Some(47));
+        assert_eq!(parse_duration("PT1H"), Some(60));
+        assert_eq!(parse_duration("PT2M11S"), Some(2));
+        assert_eq!(parse_duration("2 hours"), None);
+    }
+}
@@ -0,0 +1,408 @@
+// Modules
+pub mod http;
+pub mod imdb;
+pub mod omdb;
+pub mod tmdb;
+
+// Imports
+use eyre::OptionExt;
+use http::HttpClient;
+use itertools::Itertools;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::{fmt::Display, path::Path};
+
+static IMDB_ID_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r#"\btt\d{7,}\b"#).unwrap());
+static YEAR_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r#"\b(?:19|20)\d{2}\b"#).unwrap());
+
+#[derive(Debug)]
+pub enum Provider {
+    Imdb(imdb::Imdb),
+    Tmdb(tmdb::Tmdb),
+    Omdb(omdb::Omdb),
+}
+
+impl Provider {
+    pub const NAMES: [&'static str; 3] = ["imdb", "tmdb", "omdb"];
+
+    pub fn from_name(name: &str) -> eyre::Result<Self> {
+        match name {
+            "imdb" => Ok(Self::Imdb(imdb::Imdb::from_config())),
+            "tmdb" => Ok(Self::Tmdb(tmdb::Tmdb::from_config()?)),
+            "omdb" => Ok(Self::Omdb(omdb::Omdb::from_config()?)),
+            _ => Err(eyre::eyre!("Unknown metadata provider '{name}'")),
+        }
+    }
+
+    /// Picks the provider matching the link, bare IMDb ids are handled by IMDb
+    pub fn detect(link: &str) -> eyre::Result<Self> {
+        if link.contains("themoviedb.org") {
+            Self::from_name("tmdb")
+        } else if link.contains("omdbapi.com") {
+            Self::from_name("omdb")
+        } else if link.contains("imdb.com") || IMDB_ID_RE.is_match(link) {
+            Self::from_name("imdb")
+        } else {
+            Err(eyre::eyre!(
+                "Failed to detect the metadata provider from the link, use --provider"
+            ))
+        }
+    }
+
+    pub async fn fetch(&self, client: &HttpClient, link: &str) -> eyre::Result<Metadata> {
+        match self {
+            Self::Imdb(imdb) => imdb.fetch(client, link).await,
+            Self::Tmdb(tmdb) => tmdb.fetch(client, link).await,
+            Self::Omdb(omdb) => omdb.fetch(client, link).await,
+        }
+    }
+
+    /// Searches the provider, candidates are ranked from best to worst match
+    pub async fn search(
+        &self,
+        client: &HttpClient,
+        query: &str,
+        year: Option<&str>,
+    ) -> eyre::Result<Vec<Candidate>> {
+        let candidates = match self {
+            Self::Imdb(imdb) => imdb.search(client, query, year).await?,
+            Self::Tmdb(tmdb) => tmdb.search(client, query, year).await?,
+            Self::Omdb(omdb) => omdb.search(client, query, year).await?,
+        };
+        Ok(rank(candidates, query, year))
+    }
+
+    /// Whether the provider lists the episodes of shows
+    pub fn has_episodes(&self) -> bool {
+        match self {
+            Self::Imdb(imdb) => imdb.has_episodes(),
+            Self::Tmdb(tmdb) => tmdb.has_episodes(),
+            Self::Omdb(omdb) => omdb.has_episodes(),
+        }
+    }
+
+    /// Lists the episodes of a season of the show behind the link
+    pub async fn episodes(
+        &self,
+        client: &HttpClient,
+        link: &str,
+        season: u8,
+    ) -> eyre::Result<Vec<EpisodeMetadata>> {
+        match self {
+            Self::Imdb(imdb) => imdb.episodes(client, link, season).await,
+            Self::Tmdb(tmdb) => tmdb.episodes(client, link, season).await,
+            Self::Omdb(omdb) => omdb.episodes(client, link, season).await,
+        }
+    }
+}
+
+trait MetadataProvider {
+    /// Fetches the metadata of a title from a link or an id understood by the provider
+    async fn fetch(&self, client: &HttpClient, link: &str) -> eyre::Result<Metadata>;
+
+    /// Searches titles by name, the year is only a hint
+    async fn search(
+        &self,
+        client: &HttpClient,
+        query: &str,
+        year: Option<&str>,
+    ) -> eyre::Result<Vec<Candidate>>;
+
+    fn has_episodes(&self) -> bool {
+        false
+    }
+
+    async fn episodes(
+        &self,
+        _client: &HttpClient,
+        _link: &str,
+        _season: u8,
+    ) -> eyre::Result<Vec<EpisodeMetadata>> {
+        Err(eyre::eyre!(
+            "This provider doesn't list episodes, use --provider tmdb or omdb"
+        ))
+    }
+}
+
+// Search result, the link can be passed to the fetch method of the same provider
+#[derive(Debug, Clone, PartialEq)]
+pub struct Candidate {
+    pub title: String,
+    pub year: Option<String>,
+    // e.g. 'movie', 'TV series'
+    pub kind: Option<String>,
+    pub link: String,
+    pub score: f32,
+}
+
+impl Display for Candidate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.title)?;
+        if let Some(year) = &self.year {
+            write!(f, " ({year})")?;
+        }
+        if let Some(kind) = &self.kind {
+            write!(f, " • {kind}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Derives a search query and an optional year from the default title of a card folder
+pub fn query_from_folder_name(path: &Path) -> (String, Option<String>) {
+    let name = crate::card::title_from_folder_name(path);
+    let year = YEAR_RE.find_iter(&name).last();
+    let query = match year {
+        Some(year) => name[..year.start()].to_owned() + &name[year.end()..],
+        None => name.clone(),
+    };
+    let query = query
+        .replace(['(', ')', '[', ']'], " ")
+        .split_whitespace()
+        .join(" ");
+    (query, year.map(|year| year.as_str().to_owned()))
+}
+
+// lowercase alphanumeric words
+fn normalize(title: &str) -> Vec<String> {
+    title
+        .to_lowercase()
+        .split(|chr: char| !chr.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_owned)
+        .collect()
+}
+
+// Scores candidates on the words shared with the query and on the year, sorts them best first
+fn rank(mut candidates: Vec<Candidate>, query: &str, year: Option<&str>) -> Vec<Candidate> {
+    let query = normalize(query);
+    for candidate in candidates.iter_mut() {
+        let title = normalize(&candidate.title);
+        let mut score = if title == query {
+            1.0
+        } else {
+            let shared = title.iter().filter(|word| query.contains(word)).count() as f32;
+            let total = (title.len() + query.len()) as f32 - shared;
+            if total > 0.0 {
+                shared / total
+            } else {
+                0.0
+            }
+        };
+        let year_distance = year
+            .and_then(|year| year.parse::<i32>().ok())
+            .zip(
+                candidate
+                    .year
+                    .as_deref()
+                    .and_then(|year| year.parse::<i32>().ok()),
+            )
+            .map(|(a, b)| (a - b).abs());
+        score += match year_distance {
+            Some(0) => 0.5,
+            Some(1) => 0.25,
+            _ => 0.0,
+        };
+        candidate.score = score;
+    }
+    candidates.sort_by(|a, b| b.score.total_cmp(&a.score));
+    candidates
+}
+
+/// Returns the best candidate if it matches the query and beats the others, None if ambiguous
+pub fn best_match(candidates: &[Candidate]) -> Option<&Candidate> {
+    let (best, rest) = candidates.split_first()?;
+    (best.score >= 1.0 && rest.first().is_none_or(|next| next.score < best.score)).then_some(best)
+}
+
+// Metadata of a title as found on a provider, every field is optional as pages vary a lot
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Metadata {
+    pub title: Option<String>,
+    pub year: Option<String>,
+    pub description: Option<String>,
+    pub image_url: Option<String>,
+    pub genres: Vec<String>,
+    pub rating: Option<f32>,
+    // in minutes
+    pub runtime: Option<u32>,
+    // only fetched on demand, see Provider::episodes
+    pub episodes: Vec<EpisodeMetadata>,
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct EpisodeMetadata {
+    pub season: u8,
+    pub episode: u8,
+    pub title: Option<String>,
+    pub description: Option<String>,
+    // e.g. '1990-04-08'
+    pub air_date: Option<String>,
+}
+
+impl Display for Metadata {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} ({})",
+            self.title.as_deref().unwrap_or("Unknown title"),
+            self.year.as_deref().unwrap_or("????")
+        )?;
+        if !self.genres.is_empty() {
+            write!(f, " • {}", self.genres.join(", "))?;
+        }
+        if let Some(runtime) = self.runtime {
+            write!(f, " • {}h{:0>2}", runtime / 60, runtime % 60)?;
+        }
+        if let Some(rating) = self.rating {
+            write!(f, " • {rating}/10")?;
+        }
+        Ok(())
+    }
+}
+
+/// Extracts an IMDb id such as 'tt0111161' from a link
+fn imdb_id(link: &str) -> Option<&str> {
+    IMDB_ID_RE.find(link).map(|id| id.as_str())
+}
+
+fn get_api_key(key: &Option<String>, name: &str) -> eyre::Result<String> {
+    key.clone()
+        .filter(|key| !key.is_empty())
+        .ok_or_eyre(format!("No API key set, add '{name}' to the config"))
+}
+
+// Sends a GET request to a JSON api, error payloads aren't cached
+async fn get_json(
+    client: &HttpClient,
+    url: &str,
+    query: &[(&str, &str)],
+) -> eyre::Result<serde_json::Value> {
+    let body = client
+        .get_with(url, query, |body| {
+            serde_json::from_slice(body).is_ok_and(|value| !is_error_payload(&value))
+        })
+        .await?;
+    Ok(serde_json::from_slice(&body)?)
+}
+
+// OMDb answers errors with '"Response": "False"' and TMDB with '"success": false'
+fn is_error_payload(value: &serde_json::Value) -> bool {
+    value.get("Response").and_then(serde_json::Value::as_str) == Some("False")
+        || value.get("success").and_then(serde_json::Value::as_bool) == Some(false)
+}
+
+/// Parses an ISO 8601 duration such as 'PT2H22M' into minutes
+fn parse_duration(duration: &str) -> Option<u32> {
+    let time = duration.strip_prefix("PT")?;
+    let mut minutes = 0;
+    let mut number = String::new();
+    for chr in time.chars() {
+        match chr {
+            '0'..='9' => number.push(chr),
+            'H' => minutes += number.drain(..).as_str().parse::<u32>().ok()? * 60,
+            'M' => minutes += number.drain(..).as_str().parse::<u32>().ok()?,
+            'S' => number.clear(),
+            _ => return None,
+        }
+    }
+    Some(minutes)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// Serves the router on a random local port, returns its base url
+    pub(crate) async fn serve(router: axum::Router) -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+        format!("http://{address}")
+    }
+
+    fn candidate(title: &str, year: &str) -> Candidate {
+        Candidate {
+            title: title.to_string(),
+            year: Some(year.to_string()),
+            kind: None,
+            link: String::new(),
+            score: 0.0,
+        }
+    }
+
+    #[test]
+    fn folder_name_queries() {
+        assert_eq!(
+            query_from_folder_name(Path::new("/lib/The_Shawshank-Redemption")),
+            ("The Shawshank Redemption".to_string(), None)
+        );
+        assert_eq!(
+            query_from_folder_name(Path::new("/lib/Blade_Runner_(1982)")),
+            ("Blade Runner".to_string(), Some("1982".to_string()))
+        );
+        assert_eq!(
+            query_from_folder_name(Path::new("/lib/2001_A_Space_Odyssey_1968")),
+            ("2001 A Space Odyssey".to_string(), Some("1968".to_string()))
+        );
+    }
+
+    #[test]
+    fn ranking() {
+        let ranked = rank(
+            vec![
+                candidate("Blade Runner 2049", "2017"),
+                candidate("Blade Runner", "2007"),
+                candidate("Blade Runner", "1982"),
+                candidate("Blade", "1998"),
+            ],
+            "blade runner",
+            Some("1982"),
+        );
+        let ranked = ranked
+            .iter()
+            .map(|candidate| candidate.year.as_deref().unwrap())
+            .collect_vec();
+        assert_eq!(ranked, vec!["1982", "2007", "2017", "1998"]);
+    }
+
+    #[test]
+    fn best_matches() {
+        let candidates = vec![
+            candidate("Blade Runner", "1982"),
+            candidate("Blade Runner", "2007"),
+        ];
+        let ranked = rank(candidates.clone(), "blade runner", Some("1982"));
+        assert_eq!(best_match(&ranked).unwrap().year.as_deref(), Some("1982"));
+        // same title without a year to tell them apart
+        let ranked = rank(candidates, "blade runner", None);
+        assert_eq!(best_match(&ranked), None);
+        let ranked = rank(vec![candidate("Blade", "1998")], "blade runner", None);
+        assert_eq!(best_match(&ranked), None);
+    }
+
+    #[test]
+    fn imdb_ids() {
+        assert_eq!(
+            imdb_id("https://www.imdb.com/title/tt0111161/?ref_=nv_sr_srsg_0"),
+            Some("tt0111161")
+        );
+        assert_eq!(imdb_id("tt10872600"), Some("tt10872600"));
+        assert_eq!(imdb_id("https://www.themoviedb.org/movie/278"), None);
+    }
+
+    #[test]
+    fn error_payloads() {
+        let value = |json: &str| serde_json::from_str::<serde_json::Value>(json).unwrap();
+        assert!(is_error_payload(&value(
+            r#"{ "Response": "False", "Error": "Invalid API key!" }"#
+        )));
+        assert!(is_error_payload(&value(
+            r#"{ "success": false, "status_code": 34 }"#
+        )));
+        assert!(!is_error_payload(&value(
+            r#"{ "Response": "True", "Title": "Heat" }"#
+        )));
+        assert!(!is_error_payload(&value(r#"{ "results": [] }"#)));
+    }
+}
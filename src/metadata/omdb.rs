@@ -0,0 +1,262 @@
+use super::*;
+use crate::config::get as gcfg;
+use serde_json::Value;
+
+#[derive(Debug)]
+pub struct Omdb {
+    api_url: String,
+    api_key: String,
+}
+
+impl Omdb {
+    pub fn from_config() -> eyre::Result<Self> {
+        Ok(Self {
+            api_url: gcfg().omdb_api_url.clone(),
+            api_key: get_api_key(&gcfg().omdb_api_key, "omdb_api_key")?,
+        })
+    }
+}
+
+impl MetadataProvider for Omdb {
+    /// OMDb indexes titles by their IMDb id
+    async fn fetch(&self, client: &HttpClient, link: &str) -> eyre::Result<Metadata> {
+        let imdb_id = imdb_id(link).ok_or_eyre("Invalid IMDb link or id")?;
+        let value = get_json(
+            client,
+            &self.api_url,
+            &[("apikey", &self.api_key), ("i", imdb_id), ("plot", "full")],
+        )
+        .await?;
+
+        if value.get("Response").and_then(Value::as_str) != Some("True") {
+            return Err(eyre::eyre!(
+                "OMDb error, '{}'",
+                value
+                    .get("Error")
+                    .and_then(Value::as_str)
+                    .unwrap_or("unknown")
+            ));
+        }
+
+        // missing values are set to 'N/A'
+        let string = |key: &str| {
+            value
+                .get(key)
+                .and_then(Value::as_str)
+                .map(str::trim)
+                .filter(|string| !string.is_empty() && *string != "N/A")
+                .map(str::to_owned)
+        };
+
+        Ok(Metadata {
+            title: string("Title"),
+            // series span years, e.g. '1990–1991'
+            year: string("Year").and_then(|year| year.get(..4).map(str::to_owned)),
+            description: string("Plot"),
+            image_url: string("Poster"),
+            genres: string("Genre")
+                .map(|genres| {
+                    genres
+                        .split(',')
+                        .map(|genre| genre.trim().to_owned())
+                        .collect()
+                })
+                .unwrap_or_default(),
+            rating: string("imdbRating").and_then(|rating| rating.parse::<f32>().ok()),
+            runtime: string("Runtime")
+                .and_then(|runtime| runtime.trim_end_matches("min").trim().parse::<u32>().ok()),
+            episodes: Vec::new(),
+        })
+    }
+
+    async fn search(
+        &self,
+        client: &HttpClient,
+        query: &str,
+        year: Option<&str>,
+    ) -> eyre::Result<Vec<Candidate>> {
+        let mut params = vec![("apikey", self.api_key.as_str()), ("s", query)];
+        if let Some(year) = year {
+            params.push(("y", year));
+        }
+        let value = get_json(client, &self.api_url, &params).await?;
+
+        Ok(value
+            .get("Search")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(|result| {
+                Some(Candidate {
+                    title: result.get("Title")?.as_str()?.to_owned(),
+                    year: result
+                        .get("Year")
+                        .and_then(Value::as_str)
+                        .and_then(|year| year.get(..4))
+                        .map(str::to_owned),
+                    kind: result
+                        .get("Type")
+                        .and_then(Value::as_str)
+                        .map(str::to_owned),
+                    link: result.get("imdbID")?.as_str()?.to_owned(),
+                    score: 0.0,
+                })
+            })
+            .collect())
+    }
+
+    fn has_episodes(&self) -> bool {
+        true
+    }
+
+    async fn episodes(
+        &self,
+        client: &HttpClient,
+        link: &str,
+        season: u8,
+    ) -> eyre::Result<Vec<EpisodeMetadata>> {
+        let imdb_id = imdb_id(link).ok_or_eyre("Invalid IMDb link or id")?;
+        let value = get_json(
+            client,
+            &self.api_url,
+            &[
+                ("apikey", &self.api_key),
+                ("i", imdb_id),
+                ("Season", &season.to_string()),
+            ],
+        )
+        .await?;
+
+        let string = |episode: &Value, key: &str| {
+            episode
+                .get(key)
+                .and_then(Value::as_str)
+                .filter(|string| !string.is_empty() && *string != "N/A")
+                .map(str::to_owned)
+        };
+        // the season listing has no plots
+        Ok(value
+            .get("Episodes")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(|episode| {
+                Some(EpisodeMetadata {
+                    season,
+                    episode: string(episode, "Episode")?.parse().ok()?,
+                    title: string(episode, "Title"),
+                    description: None,
+                    air_date: string(episode, "Released"),
+                })
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{extract::Query, routing::get, Json};
+    use reqwest::Client;
+    use std::collections::HashMap;
+
+    async fn mock() -> Omdb {
+        let router = axum::Router::new().route(
+            "/",
+            get(|Query(query): Query<HashMap<String, String>>| async move {
+                assert_eq!(query["apikey"], "key");
+                if let Some(season) = query.get("Season") {
+                    assert_eq!(season, "1");
+                    return Json(serde_json::json!({
+                        "Title": "Twin Peaks",
+                        "Season": "1",
+                        "Episodes": [
+                            { "Title": "Pilot", "Released": "1990-04-08", "Episode": "1", "imdbID": "tt0098936" },
+                            { "Title": "Traces to Nowhere", "Released": "1990-04-12", "Episode": "2", "imdbID": "tt0588012" }
+                        ],
+                        "Response": "True"
+                    }));
+                }
+                if let Some(search) = query.get("s") {
+                    assert_eq!(search, "twin peaks");
+                    assert_eq!(query["y"], "1990");
+                    return Json(serde_json::json!({
+                        "Search": [{ "Title": "Twin Peaks", "Year": "1990–1991", "imdbID": "tt0098936", "Type": "series" }],
+                        "Response": "True"
+                    }));
+                }
+                match query["i"].as_str() {
+                    "tt0098936" => Json(serde_json::json!({
+                        "Title": "Twin Peaks",
+                        "Year": "1990–1991",
+                        "Runtime": "47 min",
+                        "Genre": "Crime, Drama, Mystery",
+                        "Plot": "An idiosyncratic FBI agent investigates the murder of a young woman.",
+                        "Poster": "https://m.media-amazon.com/images/M/twinpeaks.jpg",
+                        "imdbRating": "8.8",
+                        "Response": "True"
+                    })),
+                    _ => Json(serde_json::json!({
+                        "Response": "False",
+                        "Error": "Incorrect IMDb ID."
+                    })),
+                }
+            }),
+        );
+        Omdb {
+            api_url: crate::metadata::tests::serve(router).await + "/",
+            api_key: "key".to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn fetch_show() {
+        let metadata = mock()
+            .await
+            .fetch(
+                &HttpClient::new(Client::new()),
+                "https://www.imdb.com/title/tt0098936/",
+            )
+            .await
+            .unwrap();
+        assert_eq!(metadata.title.as_deref(), Some("Twin Peaks"));
+        assert_eq!(metadata.year.as_deref(), Some("1990"));
+        assert_eq!(metadata.genres, vec!["Crime", "Drama", "Mystery"]);
+        assert_eq!(metadata.rating, Some(8.8));
+        assert_eq!(metadata.runtime, Some(47));
+    }
+
+    #[tokio::test]
+    async fn search_with_year() {
+        let candidates = mock()
+            .await
+            .search(&HttpClient::new(Client::new()), "twin peaks", Some("1990"))
+            .await
+            .unwrap();
+        assert_eq!(candidates[0].to_string(), "Twin Peaks (1990) • series");
+        assert_eq!(candidates[0].link, "tt0098936");
+    }
+
+    #[tokio::test]
+    async fn season_episodes() {
+        let episodes = mock()
+            .await
+            .episodes(&HttpClient::new(Client::new()), "tt0098936", 1)
+            .await
+            .unwrap();
+        assert_eq!(episodes.len(), 2);
+        assert_eq!(episodes[1].episode, 2);
+        assert_eq!(episodes[1].title.as_deref(), Some("Traces to Nowhere"));
+        assert_eq!(episodes[1].air_date.as_deref(), Some("1990-04-12"));
+    }
+
+    #[tokio::test]
+    async fn fetch_error() {
+        let err = mock()
+            .await
+            .fetch(&HttpClient::new(Client::new()), "tt0000000")
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("Incorrect IMDb ID."));
+    }
+}
@@ -0,0 +1,340 @@
+use super::*;
+use crate::config::get as gcfg;
+use serde_json::Value;
+
+static TMDB_LINK_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"themoviedb\.org/(movie|tv)/(\d+)"#).unwrap());
+
+#[derive(Debug)]
+pub struct Tmdb {
+    api_url: String,
+    image_url: String,
+    api_key: String,
+}
+
+impl Tmdb {
+    pub fn from_config() -> eyre::Result<Self> {
+        Ok(Self {
+            api_url: gcfg().tmdb_api_url.clone(),
+            image_url: gcfg().tmdb_image_url.clone(),
+            api_key: get_api_key(&gcfg().tmdb_api_key, "tmdb_api_key")?,
+        })
+    }
+
+    // resolves the kind ('movie' or 'tv') and TMDB id of a TMDB link or an IMDb link/id
+    async fn resolve(&self, client: &HttpClient, link: &str) -> eyre::Result<(String, String)> {
+        if let Some(captures) = TMDB_LINK_RE.captures(link) {
+            return Ok((captures[1].to_owned(), captures[2].to_owned()));
+        }
+
+        let imdb_id = imdb_id(link).ok_or_eyre("Invalid TMDB or IMDb link")?;
+        let found = get_json(
+            client,
+            &format!("{}/find/{imdb_id}", self.api_url),
+            &[("api_key", &self.api_key), ("external_source", "imdb_id")],
+        )
+        .await?;
+        for (key, kind) in [("movie_results", "movie"), ("tv_results", "tv")] {
+            if let Some(id) = found
+                .pointer(&format!("/{key}/0/id"))
+                .and_then(Value::as_u64)
+            {
+                return Ok((kind.to_owned(), id.to_string()));
+            }
+        }
+        Err(eyre::eyre!("No TMDB entry found for '{imdb_id}'"))
+    }
+}
+
+impl MetadataProvider for Tmdb {
+    async fn fetch(&self, client: &HttpClient, link: &str) -> eyre::Result<Metadata> {
+        let (kind, id) = self.resolve(client, link).await?;
+        let value = get_json(
+            client,
+            &format!("{}/{kind}/{id}", self.api_url),
+            &[("api_key", &self.api_key)],
+        )
+        .await?;
+
+        let string = |key: &str| {
+            value
+                .get(key)
+                .and_then(Value::as_str)
+                .map(str::trim)
+                .filter(|string| !string.is_empty())
+                .map(str::to_owned)
+        };
+
+        Ok(Metadata {
+            // movies and shows use different keys
+            title: string("title").or_else(|| string("name")),
+            year: string("release_date")
+                .or_else(|| string("first_air_date"))
+                .and_then(|date| date.get(..4).map(str::to_owned)),
+            description: string("overview"),
+            image_url: string("poster_path").map(|path| format!("{}{path}", self.image_url)),
+            genres: value
+                .get("genres")
+                .and_then(Value::as_array)
+                .map(|genres| {
+                    genres
+                        .iter()
+                        .filter_map(|genre| genre.get("name")?.as_str().map(str::to_owned))
+                        .collect()
+                })
+                .unwrap_or_default(),
+            rating: value
+                .get("vote_average")
+                .and_then(Value::as_f64)
+                .filter(|rating| *rating > 0.0)
+                .map(|rating| rating as f32),
+            runtime: value
+                .get("runtime")
+                .or(value.pointer("/episode_run_time/0"))
+                .and_then(Value::as_u64)
+                .filter(|runtime| *runtime > 0)
+                .map(|runtime| runtime as u32),
+            episodes: Vec::new(),
+        })
+    }
+
+    async fn search(
+        &self,
+        client: &HttpClient,
+        query: &str,
+        _year: Option<&str>,
+    ) -> eyre::Result<Vec<Candidate>> {
+        let value = get_json(
+            client,
+            &format!("{}/search/multi", self.api_url),
+            &[("api_key", &self.api_key), ("query", query)],
+        )
+        .await?;
+
+        Ok(value
+            .get("results")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(|result| {
+                // multi search also returns people
+                let kind = result
+                    .get("media_type")?
+                    .as_str()
+                    .filter(|kind| ["movie", "tv"].contains(kind))?;
+                let title = result.get("title").or(result.get("name"))?.as_str()?;
+                let date = result
+                    .get("release_date")
+                    .or(result.get("first_air_date"))
+                    .and_then(Value::as_str);
+                Some(Candidate {
+                    title: title.to_owned(),
+                    year: date.and_then(|date| date.get(..4)).map(str::to_owned),
+                    kind: Some(kind.to_owned()),
+                    link: format!(
+                        "https://www.themoviedb.org/{kind}/{}",
+                        result.get("id")?.as_u64()?
+                    ),
+                    score: 0.0,
+                })
+            })
+            .collect())
+    }
+
+    fn has_episodes(&self) -> bool {
+        true
+    }
+
+    async fn episodes(
+        &self,
+        client: &HttpClient,
+        link: &str,
+        season: u8,
+    ) -> eyre::Result<Vec<EpisodeMetadata>> {
+        let (kind, id) = self.resolve(client, link).await?;
+        if kind != "tv" {
+            return Err(eyre::eyre!("'{link}' is not a show"));
+        }
+        let value = get_json(
+            client,
+            &format!("{}/tv/{id}/season/{season}", self.api_url),
+            &[("api_key", &self.api_key)],
+        )
+        .await?;
+
+        let string = |episode: &Value, key: &str| {
+            episode
+                .get(key)
+                .and_then(Value::as_str)
+                .map(str::trim)
+                .filter(|string| !string.is_empty())
+                .map(str::to_owned)
+        };
+        Ok(value
+            .get("episodes")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(|episode| {
+                Some(EpisodeMetadata {
+                    season,
+                    episode: u8::try_from(episode.get("episode_number")?.as_u64()?).ok()?,
+                    title: string(episode, "name"),
+                    description: string(episode, "overview"),
+                    air_date: string(episode, "air_date"),
+                })
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{extract::Query, routing::get, Json};
+    use reqwest::Client;
+    use std::collections::HashMap;
+
+    async fn mock() -> Tmdb {
+        let router = axum::Router::new()
+            .route(
+                "/find/tt0098936",
+                get(|Query(query): Query<HashMap<String, String>>| async move {
+                    assert_eq!(query["external_source"], "imdb_id");
+                    Json(serde_json::json!({ "movie_results": [], "tv_results": [{ "id": 1920 }] }))
+                }),
+            )
+            .route(
+                "/movie/278",
+                get(|Query(query): Query<HashMap<String, String>>| async move {
+                    assert_eq!(query["api_key"], "key");
+                    Json(serde_json::json!({
+                        "title": "The Shawshank Redemption",
+                        "release_date": "1994-09-23",
+                        "overview": "Imprisoned in the 1940s for the double murder of his wife and her lover...",
+                        "poster_path": "/9cqNxx0GxF0bflZmeSMuL5tnGzr.jpg",
+                        "genres": [{ "id": 18, "name": "Drama" }, { "id": 80, "name": "Crime" }],
+                        "vote_average": 8.7,
+                        "runtime": 142
+                    }))
+                }),
+            )
+            .route(
+                "/search/multi",
+                get(|Query(query): Query<HashMap<String, String>>| async move {
+                    assert_eq!(query["query"], "twin peaks");
+                    Json(serde_json::json!({ "results": [
+                        { "media_type": "tv", "id": 1920, "name": "Twin Peaks", "first_air_date": "1990-04-08" },
+                        { "media_type": "person", "id": 5602, "name": "David Lynch" },
+                        { "media_type": "movie", "id": 1923, "title": "Twin Peaks: Fire Walk with Me", "release_date": "1992-08-28" }
+                    ]}))
+                }),
+            )
+            .route(
+                "/tv/1920/season/2",
+                get(|| async {
+                    Json(serde_json::json!({ "season_number": 2, "episodes": [
+                        { "episode_number": 1, "name": "May the Giant Be with You", "overview": "", "air_date": "1990-09-30" },
+                        { "episode_number": 2, "name": "Coma", "overview": "Donna visits Harold Smith.", "air_date": "1990-10-06" }
+                    ]}))
+                }),
+            )
+            .route(
+                "/tv/1920",
+                get(|| async {
+                    Json(serde_json::json!({
+                        "name": "Twin Peaks",
+                        "first_air_date": "1990-04-08",
+                        "overview": "The body of Laura Palmer is washed up on a beach near the small Washington state town of Twin Peaks.",
+                        "poster_path": null,
+                        "genres": [{ "id": 9648, "name": "Mystery" }],
+                        "vote_average": 8.1,
+                        "episode_run_time": [47]
+                    }))
+                }),
+            );
+        Tmdb {
+            api_url: crate::metadata::tests::serve(router).await,
+            image_url: "https://image.tmdb.org/t/p/original".to_string(),
+            api_key: "key".to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn fetch_movie() {
+        let metadata = mock()
+            .await
+            .fetch(
+                &HttpClient::new(Client::new()),
+                "https://www.themoviedb.org/movie/278-the-shawshank-redemption",
+            )
+            .await
+            .unwrap();
+        assert_eq!(metadata.title.as_deref(), Some("The Shawshank Redemption"));
+        assert_eq!(metadata.year.as_deref(), Some("1994"));
+        assert_eq!(
+            metadata.image_url.as_deref(),
+            Some("https://image.tmdb.org/t/p/original/9cqNxx0GxF0bflZmeSMuL5tnGzr.jpg")
+        );
+        assert_eq!(metadata.genres, vec!["Drama", "Crime"]);
+        assert_eq!(metadata.rating, Some(8.7));
+        assert_eq!(metadata.runtime, Some(142));
+    }
+
+    #[tokio::test]
+    async fn fetch_show_from_imdb_id() {
+        let metadata = mock()
+            .await
+            .fetch(
+                &HttpClient::new(Client::new()),
+                "https://www.imdb.com/title/tt0098936/",
+            )
+            .await
+            .unwrap();
+        assert_eq!(metadata.title.as_deref(), Some("Twin Peaks"));
+        assert_eq!(metadata.year.as_deref(), Some("1990"));
+        assert_eq!(metadata.image_url, None);
+        assert_eq!(metadata.runtime, Some(47));
+    }
+
+    #[tokio::test]
+    async fn search_multi() {
+        let candidates = mock()
+            .await
+            .search(&HttpClient::new(Client::new()), "twin peaks", None)
+            .await
+            .unwrap();
+        assert_eq!(candidates.len(), 2);
+        assert_eq!(candidates[0].to_string(), "Twin Peaks (1990) • tv");
+        assert_eq!(candidates[0].link, "https://www.themoviedb.org/tv/1920");
+    }
+
+    #[tokio::test]
+    async fn season_episodes() {
+        let episodes = mock()
+            .await
+            .episodes(
+                &HttpClient::new(Client::new()),
+                "https://www.themoviedb.org/tv/1920-twin-peaks",
+                2,
+            )
+            .await
+            .unwrap();
+        assert_eq!(episodes.len(), 2);
+        assert_eq!(episodes[0].description, None);
+        assert_eq!(episodes[1].title.as_deref(), Some("Coma"));
+        assert_eq!(episodes[1].season, 2);
+    }
+
+    #[tokio::test]
+    async fn fetch_unknown() {
+        let result = mock()
+            .await
+            .fetch(
+                &HttpClient::new(Client::new()),
+                "https://www.themoviedb.org/movie/1",
+            )
+            .await;
+        assert!(result.is_err());
+    }
+}
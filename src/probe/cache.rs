@@ -0,0 +1,142 @@
+use super::*;
+use once_cell::sync::Lazy;
+use std::{
+    collections::HashMap,
+    path::PathBuf,
+    sync::Mutex,
+    time::{SystemTime, UNIX_EPOCH},
+};
+use tracing::{debug, warn};
+
+static CACHE: Lazy<Mutex<Cache>> = Lazy::new(|| Mutex::new(Cache::load()));
+
+// Probed videos keyed by absolute path, only valid as long as the size and mtime match
+#[derive(Default)]
+struct Cache {
+    entries: HashMap<PathBuf, Entry>,
+    // paths looked up since the last save, the others belong to removed videos
+    used: HashMap<PathBuf, Entry>,
+    dirty: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Entry {
+    size: u64,
+    // seconds since the unix epoch
+    modified: u64,
+    info: MediaInfo,
+}
+
+impl Cache {
+    // None when the directories aren't initialized, the cache then only lives in memory
+    fn fp() -> Option<PathBuf> {
+        crate::dirs::try_get().map(|dirs| dirs.data_local_dir().join("probe_cache.json"))
+    }
+
+    fn load() -> Self {
+        let entries = Self::fp()
+            .and_then(|fp| std::fs::read(fp).ok())
+            .and_then(|bytes| {
+                serde_json::from_slice(&bytes)
+                    .inspect_err(|err| warn!("Failed to parse the probe cache, '{err}'"))
+                    .ok()
+            })
+            .unwrap_or_default();
+        Self {
+            entries,
+            ..Default::default()
+        }
+    }
+}
+
+/// Probes a video, reusing the previous result if the file hasn't changed since.
+/// None if the container isn't supported or can't be parsed
+pub fn probe_cached(fp: &Path) -> Option<MediaInfo> {
+    let metadata = fp.metadata().ok()?;
+    let size = metadata.len();
+    let modified = metadata
+        .modified()
+        .ok()
+        .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |modified| modified.as_secs());
+
+    {
+        let mut cache = CACHE.lock().unwrap_or_else(|err| err.into_inner());
+        let cached = cache
+            .entries
+            .get(fp)
+            .filter(|entry| entry.size == size && entry.modified == modified)
+            .cloned();
+        if let Some(entry) = cached {
+            let info = entry.info.clone();
+            cache.used.insert(fp.to_owned(), entry);
+            return (info != MediaInfo::default()).then_some(info);
+        }
+    }
+
+    // probed outside of the lock, reading the headers may take a while on network drives
+    let started = SystemTime::now();
+    let info = match probe(fp) {
+        Ok(info) => info,
+        Err(err) => {
+            debug!("Failed to probe '{}', '{err}'", fp.display());
+            MediaInfo::default()
+        }
+    };
+    debug!(
+        "Probed '{}' in {}ms",
+        fp.display(),
+        started.elapsed().unwrap_or_default().as_millis()
+    );
+
+    let entry = Entry {
+        size,
+        modified,
+        info: info.clone(),
+    };
+    let mut cache = CACHE.lock().unwrap_or_else(|err| err.into_inner());
+    cache.entries.insert(fp.to_owned(), entry.clone());
+    cache.used.insert(fp.to_owned(), entry);
+    cache.dirty = true;
+    (info != MediaInfo::default()).then_some(info)
+}
+
+/// Writes the cache to disk. When pruning, the videos that weren't looked up since the last
+/// save are dropped, which is only right after every video of the library was probed
+pub fn save_cache(prune: bool) -> eyre::Result<()> {
+    let mut cache = CACHE.lock().unwrap_or_else(|err| err.into_inner());
+    let used = std::mem::take(&mut cache.used);
+    let pruned = prune && used.len() != cache.entries.len();
+    if !cache.dirty && !pruned {
+        return Ok(());
+    }
+    if pruned {
+        cache.entries = used;
+    }
+    let Some(fp) = Cache::fp() else {
+        return Ok(());
+    };
+    let bytes = serde_json::to_vec(&cache.entries)?;
+    if let Some(dir) = fp.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    crate::utils::write_atomic(&fp, &bytes)?;
+    cache.dirty = false;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unsupported_video_from_cache() {
+        let tmp = tempfile::tempdir().unwrap();
+        let fp = tmp.path().join("video.mkv");
+        std::fs::write(&fp, "not a video").unwrap();
+
+        assert_eq!(probe_cached(&fp), None);
+        // the failed probe is cached, the second lookup is a hit
+        assert_eq!(probe_cached(&fp), None);
+    }
+}
@@ -0,0 +1,323 @@
+use super::*;
+use std::io::SeekFrom;
+
+// Element ids, with their length marker bits
+const EBML: u32 = 0x1A45_DFA3;
+const SEGMENT: u32 = 0x1853_8067;
+const INFO: u32 = 0x1549_A966;
+const TIMESTAMP_SCALE: u32 = 0x2A_D7B1;
+const DURATION: u32 = 0x4489;
+const TRACKS: u32 = 0x1654_AE6B;
+const TRACK_ENTRY: u32 = 0xAE;
+const TRACK_TYPE: u32 = 0x83;
+const CODEC_ID: u32 = 0x86;
+const LANGUAGE: u32 = 0x22_B59C;
+const VIDEO: u32 = 0xE0;
+const PIXEL_WIDTH: u32 = 0xB0;
+const PIXEL_HEIGHT: u32 = 0xBA;
+const CLUSTER: u32 = 0x1F43_B675;
+
+// 'Info' and 'Tracks' are small, anything bigger is most likely a corrupt size
+static MAX_ELEMENT_SIZE: u64 = 16 * 1024 * 1024;
+
+/// Matroska and WebM files, the 'Info' and 'Tracks' elements precede the media clusters
+#[derive(Debug)]
+pub struct Matroska;
+
+impl ContainerParser for Matroska {
+    fn probe<R: Read + Seek>(&self, reader: &mut R) -> eyre::Result<MediaInfo> {
+        let (id, size) = read_header(reader)?;
+        if id != EBML {
+            return Err(eyre::eyre!("Not an EBML file"));
+        }
+        skip(reader, size)?;
+        let (id, _) = read_header(reader)?;
+        if id != SEGMENT {
+            return Err(eyre::eyre!("No segment found"));
+        }
+
+        let mut info = MediaInfo::default();
+        let (mut has_info, mut has_tracks) = (false, false);
+        // the segment size is often unknown, its children are read until the end of the file
+        while !(has_info && has_tracks) {
+            let Ok((id, size)) = read_header(reader) else {
+                break;
+            };
+            match id {
+                INFO => {
+                    read_info(&read_payload(reader, size)?, &mut info);
+                    has_info = true;
+                }
+                TRACKS => {
+                    read_tracks(&read_payload(reader, size)?, &mut info);
+                    has_tracks = true;
+                }
+                // the headers come first, nothing more is to be found
+                CLUSTER => break,
+                _ => skip(reader, size)?,
+            }
+        }
+
+        if !has_tracks {
+            return Err(eyre::eyre!("No tracks found"));
+        }
+        Ok(info)
+    }
+}
+
+// Id and size of the next element, None for the size if it's unknown
+fn read_header<R: Read>(reader: &mut R) -> eyre::Result<(u32, Option<u64>)> {
+    let (id, _) = read_vint(reader, true)?;
+    let (size, unknown) = read_vint(reader, false)?;
+    Ok((u32::try_from(id)?, (!unknown).then_some(size)))
+}
+
+// Variable length integer, the id keeps its marker bits. Also returns whether every value
+// bit is set, which marks an unknown size
+fn read_vint<R: Read>(reader: &mut R, keep_marker: bool) -> eyre::Result<(u64, bool)> {
+    let mut first = [0_u8; 1];
+    reader.read_exact(&mut first)?;
+    let len = first[0].leading_zeros() as usize + 1;
+    if len > 8 {
+        return Err(eyre::eyre!("Invalid variable length integer"));
+    }
+    let mut rest = [0_u8; 7];
+    reader.read_exact(&mut rest[..len - 1])?;
+
+    let marker = if keep_marker {
+        0xff
+    } else {
+        (0xff_u16 >> len) as u8
+    };
+    let value = rest[..len - 1]
+        .iter()
+        .fold(u64::from(first[0] & marker), |acc, byte| {
+            (acc << 8) | u64::from(*byte)
+        });
+    let max = (1_u64 << (7 * len)) - 1;
+    Ok((value, !keep_marker && value == max))
+}
+
+fn skip<R: Seek>(reader: &mut R, size: Option<u64>) -> eyre::Result<()> {
+    let size = size.ok_or_else(|| eyre::eyre!("Can't skip an element of unknown size"))?;
+    reader.seek(SeekFrom::Current(i64::try_from(size)?))?;
+    Ok(())
+}
+
+fn read_payload<R: Read>(reader: &mut R, size: Option<u64>) -> eyre::Result<Vec<u8>> {
+    let size = size
+        .filter(|size| *size <= MAX_ELEMENT_SIZE)
+        .ok_or_else(|| eyre::eyre!("Invalid element size"))?;
+    let mut payload = vec![0_u8; size as usize];
+    reader.read_exact(&mut payload)?;
+    Ok(payload)
+}
+
+// Iterates over the child elements of an in-memory payload, stops at the first malformed one
+fn elements(mut bytes: &[u8]) -> impl Iterator<Item = (u32, &[u8])> {
+    std::iter::from_fn(move || {
+        let mut cursor = std::io::Cursor::new(bytes);
+        let (id, size) = read_header(&mut cursor).ok()?;
+        let start = usize::try_from(cursor.position()).ok()?;
+        let end = start.checked_add(usize::try_from(size?).ok()?)?;
+        let payload = bytes.get(start..end)?;
+        bytes = &bytes[end..];
+        Some((id, payload))
+    })
+}
+
+fn read_uint(bytes: &[u8]) -> Option<u64> {
+    (bytes.len() <= 8).then(|| {
+        bytes
+            .iter()
+            .fold(0, |acc, byte| (acc << 8) | u64::from(*byte))
+    })
+}
+
+fn read_float(bytes: &[u8]) -> Option<f64> {
+    match bytes.len() {
+        4 => Some(f64::from(f32::from_be_bytes(bytes.try_into().ok()?))),
+        8 => Some(f64::from_be_bytes(bytes.try_into().ok()?)),
+        _ => None,
+    }
+}
+
+fn read_string(bytes: &[u8]) -> String {
+    String::from_utf8_lossy(bytes)
+        .trim_end_matches('\0')
+        .to_owned()
+}
+
+fn read_info(payload: &[u8], info: &mut MediaInfo) {
+    // in nanoseconds per tick
+    let mut scale = 1_000_000;
+    let mut duration = None;
+    for (id, bytes) in elements(payload) {
+        match id {
+            TIMESTAMP_SCALE => scale = read_uint(bytes).unwrap_or(scale),
+            DURATION => duration = read_float(bytes),
+            _ => (),
+        }
+    }
+    info.duration = duration
+        .filter(|duration| duration.is_finite() && *duration > 0.0)
+        .map(|duration| (duration * scale as f64 / 1e9) as u64);
+}
+
+fn read_tracks(payload: &[u8], info: &mut MediaInfo) {
+    for (_, entry) in elements(payload).filter(|(id, _)| *id == TRACK_ENTRY) {
+        let (mut kind, mut codec, mut size) = (0, String::new(), (None, None));
+        // the default language of a track is english
+        let mut language = Some("eng".to_owned());
+        for (id, bytes) in elements(entry) {
+            match id {
+                TRACK_TYPE => kind = read_uint(bytes).unwrap_or_default(),
+                CODEC_ID => codec = read_string(bytes),
+                LANGUAGE => language = known_language(&read_string(bytes)),
+                VIDEO => {
+                    for (id, bytes) in elements(bytes) {
+                        match id {
+                            PIXEL_WIDTH => size.0 = read_uint(bytes),
+                            PIXEL_HEIGHT => size.1 = read_uint(bytes),
+                            _ => (),
+                        }
+                    }
+                }
+                _ => (),
+            }
+        }
+
+        match kind {
+            1 if info.video_codec.is_none() => {
+                info.video_codec = Some(codec_name(&codec));
+                info.width = size.0.and_then(|width| u32::try_from(width).ok());
+                info.height = size.1.and_then(|height| u32::try_from(height).ok());
+            }
+            2 => info.audio_tracks.push(Track {
+                codec: codec_name(&codec),
+                language,
+            }),
+            17 => info.subtitle_tracks.push(Track {
+                codec: codec_name(&codec),
+                language,
+            }),
+            _ => (),
+        }
+    }
+}
+
+// Matroska codec ids into the usual codec names
+fn codec_name(codec_id: &str) -> String {
+    match codec_id {
+        "V_MPEG4/ISO/AVC" => "H.264",
+        "V_MPEGH/ISO/HEVC" => "HEVC",
+        "V_AV1" => "AV1",
+        "V_VP9" => "VP9",
+        "V_VP8" => "VP8",
+        "V_MPEG4/ISO/ASP" | "V_MPEG4/ISO/SP" => "MPEG-4",
+        "A_AC3" => "AC3",
+        "A_EAC3" => "EAC3",
+        "A_DTS" => "DTS",
+        "A_OPUS" => "Opus",
+        "A_VORBIS" => "Vorbis",
+        "A_FLAC" => "FLAC",
+        "A_TRUEHD" => "TrueHD",
+        "A_MPEG/L3" => "MP3",
+        "S_TEXT/UTF8" => "SRT",
+        "S_TEXT/ASS" | "S_TEXT/SSA" => "ASS",
+        "S_TEXT/WEBVTT" => "WebVTT",
+        "S_HDMV/PGS" => "PGS",
+        "S_VOBSUB" => "VobSub",
+        codec_id if codec_id.starts_with("A_AAC") => "AAC",
+        codec_id => return codec_id.to_owned(),
+    }
+    .to_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    // sizes are always written on 8 bytes, which is valid if wasteful
+    fn element(id: u32, payload: &[u8]) -> Vec<u8> {
+        let mut bytes = id
+            .to_be_bytes()
+            .into_iter()
+            .skip_while(|byte| *byte == 0)
+            .collect_vec();
+        bytes.push(0x01);
+        bytes.extend_from_slice(&(payload.len() as u64).to_be_bytes()[1..]);
+        bytes.extend_from_slice(payload);
+        bytes
+    }
+
+    fn track(kind: u8, codec: &str, language: Option<&str>, video: Option<(u16, u16)>) -> Vec<u8> {
+        let mut entry = [
+            element(TRACK_TYPE, &[kind]),
+            element(CODEC_ID, codec.as_bytes()),
+        ]
+        .concat();
+        if let Some(language) = language {
+            entry.extend(element(LANGUAGE, language.as_bytes()));
+        }
+        if let Some((width, height)) = video {
+            entry.extend(element(
+                VIDEO,
+                &[
+                    element(PIXEL_WIDTH, &width.to_be_bytes()),
+                    element(PIXEL_HEIGHT, &height.to_be_bytes()),
+                ]
+                .concat(),
+            ));
+        }
+        element(TRACK_ENTRY, &entry)
+    }
+
+    #[test]
+    fn webm_headers() {
+        let info = element(
+            INFO,
+            &[
+                element(TIMESTAMP_SCALE, &1_000_000_u32.to_be_bytes()[1..]),
+                // 42 minutes in milliseconds
+                element(DURATION, &2_520_000.5_f64.to_be_bytes()),
+            ]
+            .concat(),
+        );
+        let tracks = element(
+            TRACKS,
+            &[
+                track(1, "V_MPEG4/ISO/AVC", None, Some((1280, 720))),
+                track(2, "A_AAC/MPEG4/LC", None, None),
+                track(2, "A_OPUS", Some("jpn"), None),
+                track(17, "S_TEXT/UTF8", Some("fre"), None),
+            ]
+            .concat(),
+        );
+        // unknown segment size
+        let mut segment = vec![
+            0x18, 0x53, 0x80, 0x67, 0x01, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+        ];
+        segment.extend(element(0xEC, &[0; 32]));
+        segment.extend(info);
+        segment.extend(tracks);
+        segment.extend(element(CLUSTER, &[0; 64]));
+        let file = [element(EBML, &element(0x4282, b"webm")), segment].concat();
+
+        let info = Matroska.probe(&mut Cursor::new(file)).unwrap();
+        assert_eq!(info.duration, Some(2520));
+        assert_eq!(info.resolution().as_deref(), Some("720p"));
+        assert_eq!(info.video_codec.as_deref(), Some("H.264"));
+        assert_eq!(info.audio_languages(), vec!["eng", "jpn"]);
+        assert_eq!(info.subtitle_tracks[0].codec, "SRT");
+        assert_eq!(
+            info.to_html_string(),
+            "42min • 720p • H.264 • eng, jpn • Subs fre"
+        );
+
+        assert!(Matroska
+            .probe(&mut Cursor::new(b"not a video".to_vec()))
+            .is_err());
+    }
+}
@@ -0,0 +1,149 @@
+// Modules
+mod cache;
+mod mkv;
+mod mp4;
+
+// Imports
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::File,
+    io::{BufReader, Read, Seek},
+    path::Path,
+};
+
+pub use cache::{probe_cached, save_cache};
+
+/// Technical details of a video, read from the headers of its container
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MediaInfo {
+    // in seconds
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub width: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub height: Option<u32>,
+    // e.g. 'HEVC', the raw codec id if it isn't known
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub video_codec: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub audio_tracks: Vec<Track>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub subtitle_tracks: Vec<Track>,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Track {
+    pub codec: String,
+    // ISO 639-2 code such as 'eng', None if undetermined
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+}
+
+#[derive(Debug)]
+enum Container {
+    Mp4(mp4::Mp4),
+    Matroska(mkv::Matroska),
+}
+
+impl Container {
+    fn from_extension(extension: &str) -> Option<Self> {
+        match extension.to_ascii_lowercase().as_str() {
+            "mp4" | "m4v" | "mov" | "qt" => Some(Self::Mp4(mp4::Mp4)),
+            "mkv" | "webm" => Some(Self::Matroska(mkv::Matroska)),
+            _ => None,
+        }
+    }
+
+    fn probe<R: Read + Seek>(&self, reader: &mut R) -> eyre::Result<MediaInfo> {
+        match self {
+            Self::Mp4(mp4) => mp4.probe(reader),
+            Self::Matroska(matroska) => matroska.probe(reader),
+        }
+    }
+}
+
+trait ContainerParser {
+    fn probe<R: Read + Seek>(&self, reader: &mut R) -> eyre::Result<MediaInfo>;
+}
+
+/// Reads the headers of a video, only MP4/MOV and Matroska/WebM are supported
+pub fn probe(fp: &Path) -> eyre::Result<MediaInfo> {
+    let container = Container::from_extension(crate::utils::get_extension(fp))
+        .ok_or_else(|| eyre::eyre!("Unsupported container"))?;
+    let mut reader = BufReader::new(File::open(fp)?);
+    container.probe(&mut reader)
+}
+
+impl MediaInfo {
+    /// Common name of the resolution, e.g. '1080p', based on the width so that cropped
+    /// widescreen videos aren't underrated
+    pub fn resolution(&self) -> Option<String> {
+        let (width, height) = (self.width?, self.height?);
+        Some(match width {
+            3800.. => "2160p".to_owned(),
+            2500.. => "1440p".to_owned(),
+            1900.. => "1080p".to_owned(),
+            1200.. => "720p".to_owned(),
+            _ => format!("{height}p"),
+        })
+    }
+
+    /// Languages of the audio tracks, deduplicated
+    pub fn audio_languages(&self) -> Vec<&str> {
+        languages(&self.audio_tracks)
+    }
+
+    /// Languages of the embedded subtitle tracks, deduplicated
+    pub fn subtitle_languages(&self) -> Vec<&str> {
+        languages(&self.subtitle_tracks)
+    }
+
+    /// Short summary for the cards such as '1h52 • 1080p • HEVC • eng, fre • Subs eng',
+    /// empty if nothing is known
+    pub fn to_html_string(&self) -> String {
+        let languages = self.audio_languages().join(", ");
+        // embedded tracks, sidecar files are listed separately with 'CC'
+        let subtitles = (!self.subtitle_tracks.is_empty()).then(|| {
+            let languages = self.subtitle_languages().join(", ");
+            format!("Subs {languages}").trim_end().to_owned()
+        });
+        [
+            self.duration.map(|duration| {
+                let minutes = duration / 60;
+                match minutes {
+                    0..60 => format!("{minutes}min"),
+                    _ => format!("{}h{:0>2}", minutes / 60, minutes % 60),
+                }
+            }),
+            self.resolution(),
+            self.video_codec.clone(),
+            Some(languages).filter(|languages| !languages.is_empty()),
+            subtitles,
+        ]
+        .into_iter()
+        .flatten()
+        .map(|part| crate::html::text(&part))
+        .join(" • ")
+    }
+}
+
+fn languages(tracks: &[Track]) -> Vec<&str> {
+    tracks
+        .iter()
+        .filter_map(|track| track.language.as_deref())
+        .unique()
+        .collect()
+}
+
+// ISO 639-2 codes meaning that the language isn't known
+fn known_language(language: &str) -> Option<String> {
+    let language = language.trim_end_matches('\0').trim();
+    match language {
+        "" | "und" | "zxx" | "mis" | "mul" => None,
+        language => Some(language.to_owned()),
+    }
+}
@@ -0,0 +1,293 @@
+use super::*;
+use std::io::SeekFrom;
+
+// 'moov' is usually small, anything bigger is most likely a corrupt size
+static MAX_MOOV_SIZE: u64 = 64 * 1024 * 1024;
+
+/// ISO base media files (MP4, MOV), everything needed is in the 'moov' atom
+#[derive(Debug)]
+pub struct Mp4;
+
+impl ContainerParser for Mp4 {
+    fn probe<R: Read + Seek>(&self, reader: &mut R) -> eyre::Result<MediaInfo> {
+        let moov = find_moov(reader)?;
+        let mut info = MediaInfo::default();
+
+        for (kind, payload) in atoms(&moov) {
+            match kind {
+                b"mvhd" => info.duration = mvhd_duration(payload),
+                b"trak" => read_trak(payload, &mut info),
+                _ => (),
+            }
+        }
+        Ok(info)
+    }
+}
+
+// Walks the top-level atoms, 'mdat' is skipped without being read as 'moov' is
+// commonly placed after it
+fn find_moov<R: Read + Seek>(reader: &mut R) -> eyre::Result<Vec<u8>> {
+    loop {
+        let mut header = [0_u8; 8];
+        reader
+            .read_exact(&mut header)
+            .map_err(|_| eyre::eyre!("No 'moov' atom found"))?;
+        let size = u64::from(u32::from_be_bytes(header[..4].try_into()?));
+        let payload_len = match size {
+            1 => {
+                let mut large = [0_u8; 8];
+                reader.read_exact(&mut large)?;
+                u64::from_be_bytes(large).checked_sub(16)
+            }
+            // extends to the end of the file
+            0 => Some(remaining_len(reader)?),
+            size => size.checked_sub(8),
+        }
+        .ok_or_else(|| eyre::eyre!("Invalid atom size"))?;
+
+        if &header[4..] == b"moov" {
+            if payload_len > MAX_MOOV_SIZE {
+                return Err(eyre::eyre!("'moov' atom is too large"));
+            }
+            let mut moov = vec![0_u8; payload_len as usize];
+            reader.read_exact(&mut moov)?;
+            return Ok(moov);
+        }
+        reader.seek(SeekFrom::Current(i64::try_from(payload_len)?))?;
+    }
+}
+
+// Number of bytes between the current position and the end of the stream
+fn remaining_len<R: Seek>(reader: &mut R) -> eyre::Result<u64> {
+    let position = reader.stream_position()?;
+    let end = reader.seek(SeekFrom::End(0))?;
+    reader.seek(SeekFrom::Start(position))?;
+    Ok(end.saturating_sub(position))
+}
+
+// Iterates over the child atoms of an in-memory payload, stops at the first malformed one
+fn atoms(mut bytes: &[u8]) -> impl Iterator<Item = (&[u8; 4], &[u8])> {
+    std::iter::from_fn(move || {
+        let size = read_u32(bytes, 0)? as usize;
+        let kind: &[u8; 4] = bytes.get(4..8)?.try_into().ok()?;
+        let (header_len, size) = match size {
+            1 => (16, usize::try_from(read_u64(bytes, 8)?).ok()?),
+            0 => (8, bytes.len()),
+            size => (8, size),
+        };
+        let payload = bytes.get(header_len..size)?;
+        bytes = &bytes[size..];
+        Some((kind, payload))
+    })
+}
+
+fn child<'a>(bytes: &'a [u8], kind: &[u8; 4]) -> Option<&'a [u8]> {
+    atoms(bytes)
+        .find(|(child, _)| *child == kind)
+        .map(|(_, payload)| payload)
+}
+
+fn read_u16(bytes: &[u8], offset: usize) -> Option<u16> {
+    Some(u16::from_be_bytes(
+        bytes.get(offset..offset + 2)?.try_into().ok()?,
+    ))
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(
+        bytes.get(offset..offset + 4)?.try_into().ok()?,
+    ))
+}
+
+fn read_u64(bytes: &[u8], offset: usize) -> Option<u64> {
+    Some(u64::from_be_bytes(
+        bytes.get(offset..offset + 8)?.try_into().ok()?,
+    ))
+}
+
+// Timescale and duration of 'mvhd' and 'mdhd', the layout depends on the version
+fn timescale_and_duration(payload: &[u8]) -> Option<(u32, u64)> {
+    match payload.first()? {
+        0 => Some((read_u32(payload, 12)?, u64::from(read_u32(payload, 16)?))),
+        1 => Some((read_u32(payload, 20)?, read_u64(payload, 24)?)),
+        _ => None,
+    }
+}
+
+fn mvhd_duration(payload: &[u8]) -> Option<u64> {
+    let (timescale, duration) = timescale_and_duration(payload)?;
+    (timescale > 0 && duration != u64::from(u32::MAX) && duration != u64::MAX)
+        .then(|| duration / u64::from(timescale))
+}
+
+fn read_trak(trak: &[u8], info: &mut MediaInfo) {
+    let Some(mdia) = child(trak, b"mdia") else {
+        return;
+    };
+    let Some(handler) = child(mdia, b"hdlr").and_then(|hdlr| hdlr.get(8..12)) else {
+        return;
+    };
+    let language = child(mdia, b"mdhd").and_then(mdhd_language);
+    let codec = child(mdia, b"minf")
+        .and_then(|minf| child(minf, b"stbl"))
+        .and_then(|stbl| child(stbl, b"stsd"))
+        .and_then(|stsd| stsd.get(12..16));
+
+    match handler {
+        b"vide" if info.video_codec.is_none() => {
+            info.video_codec = codec.map(codec_name);
+            // 16.16 fixed point presentation size at the end of 'tkhd'
+            if let Some(tkhd) = child(trak, b"tkhd").filter(|tkhd| tkhd.len() >= 8) {
+                let width = read_u32(tkhd, tkhd.len() - 8).map(|width| width >> 16);
+                let height = read_u32(tkhd, tkhd.len() - 4).map(|height| height >> 16);
+                info.width = width.filter(|width| *width > 0);
+                info.height = height.filter(|height| *height > 0);
+            }
+        }
+        b"soun" => info.audio_tracks.push(Track {
+            codec: codec.map(codec_name).unwrap_or_default(),
+            language,
+        }),
+        b"sbtl" | b"subt" | b"text" => info.subtitle_tracks.push(Track {
+            codec: codec.map(codec_name).unwrap_or_default(),
+            language,
+        }),
+        _ => (),
+    }
+}
+
+// Three 5-bit letters offset by 0x60, packed after the duration
+fn mdhd_language(mdhd: &[u8]) -> Option<String> {
+    let offset = match mdhd.first()? {
+        0 => 20,
+        1 => 32,
+        _ => return None,
+    };
+    let packed = read_u16(mdhd, offset)?;
+    let language: String = [10, 5, 0]
+        .into_iter()
+        .map(|shift| char::from((((packed >> shift) & 0x1f) as u8) + 0x60))
+        .collect();
+    known_language(&language)
+        .filter(|language| language.chars().all(|chr| chr.is_ascii_lowercase()))
+}
+
+// Sample entry types into the usual codec names
+fn codec_name(fourcc: &[u8]) -> String {
+    match fourcc {
+        b"avc1" | b"avc3" => "H.264",
+        b"hvc1" | b"hev1" => "HEVC",
+        b"av01" => "AV1",
+        b"vp09" => "VP9",
+        b"vp08" => "VP8",
+        b"mp4v" => "MPEG-4",
+        b"mp4a" => "AAC",
+        b"ac-3" => "AC3",
+        b"ec-3" => "EAC3",
+        b"Opus" => "Opus",
+        b"fLaC" => "FLAC",
+        b".mp3" => "MP3",
+        b"tx3g" => "mov_text",
+        b"wvtt" => "WebVTT",
+        fourcc => return String::from_utf8_lossy(fourcc).trim().to_owned(),
+    }
+    .to_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn atom(kind: &[u8; 4], payload: &[u8]) -> Vec<u8> {
+        let mut bytes = (8 + payload.len() as u32).to_be_bytes().to_vec();
+        bytes.extend_from_slice(kind);
+        bytes.extend_from_slice(payload);
+        bytes
+    }
+
+    fn trak(handler: &[u8; 4], codec: &[u8; 4], language: &str, size: (u32, u32)) -> Vec<u8> {
+        let mut tkhd = vec![0_u8; 76];
+        tkhd.extend_from_slice(&(size.0 << 16).to_be_bytes());
+        tkhd.extend_from_slice(&(size.1 << 16).to_be_bytes());
+
+        let packed = language
+            .bytes()
+            .fold(0_u16, |acc, byte| (acc << 5) | u16::from(byte - 0x60));
+        let mut mdhd = vec![0_u8; 20];
+        mdhd.extend_from_slice(&packed.to_be_bytes());
+        mdhd.extend_from_slice(&[0, 0]);
+
+        let mut hdlr = vec![0_u8; 8];
+        hdlr.extend_from_slice(handler);
+        hdlr.extend_from_slice(&[0; 12]);
+
+        let mut stsd = vec![0, 0, 0, 0, 0, 0, 0, 1];
+        stsd.extend(atom(codec, &[0; 8]));
+
+        let stbl = atom(b"stbl", &atom(b"stsd", &stsd));
+        let minf = atom(b"minf", &stbl);
+        let mdia = atom(
+            b"mdia",
+            &[atom(b"mdhd", &mdhd), atom(b"hdlr", &hdlr), minf].concat(),
+        );
+        atom(b"trak", &[atom(b"tkhd", &tkhd), mdia].concat())
+    }
+
+    #[test]
+    fn moov_after_mdat() {
+        // version 0 'mvhd', timescale 1000 and a duration of 1h52
+        let mut mvhd = vec![0_u8; 12];
+        mvhd.extend_from_slice(&1000_u32.to_be_bytes());
+        mvhd.extend_from_slice(&(6_720_000_u32).to_be_bytes());
+        mvhd.extend_from_slice(&[0; 80]);
+
+        let moov = atom(
+            b"moov",
+            &[
+                atom(b"mvhd", &mvhd),
+                trak(b"vide", b"hvc1", "und", (1920, 800)),
+                trak(b"soun", b"mp4a", "eng", (0, 0)),
+                trak(b"soun", b"ac-3", "fra", (0, 0)),
+                trak(b"sbtl", b"tx3g", "eng", (0, 0)),
+            ]
+            .concat(),
+        );
+        let file = [
+            atom(b"ftyp", b"isom\0\0\0\0"),
+            atom(b"mdat", &[0; 1024]),
+            moov,
+        ]
+        .concat();
+
+        let info = Mp4.probe(&mut Cursor::new(file)).unwrap();
+        assert_eq!(info.duration, Some(6720));
+        assert_eq!(info.resolution().as_deref(), Some("1080p"));
+        assert_eq!(info.video_codec.as_deref(), Some("HEVC"));
+        assert_eq!(info.audio_languages(), vec!["eng", "fra"]);
+        assert_eq!(info.audio_tracks[1].codec, "AC3");
+        assert_eq!(info.subtitle_tracks.len(), 1);
+        assert_eq!(
+            info.to_html_string(),
+            "1h52 • 1080p • HEVC • eng, fra • Subs eng"
+        );
+
+        assert!(Mp4.probe(&mut Cursor::new(atom(b"ftyp", b"isom"))).is_err());
+    }
+
+    #[test]
+    fn moov_to_end_of_file() {
+        let mut mvhd = vec![0_u8; 12];
+        mvhd.extend_from_slice(&600_u32.to_be_bytes());
+        mvhd.extend_from_slice(&(600_u32 * 90).to_be_bytes());
+        mvhd.extend_from_slice(&[0; 80]);
+
+        // a size of 0 means that the atom extends to the end of the file
+        let mut moov = atom(b"moov", &atom(b"mvhd", &mvhd));
+        moov[..4].copy_from_slice(&[0; 4]);
+        let file = [atom(b"ftyp", b"isom\0\0\0\0"), moov].concat();
+
+        let info = Mp4.probe(&mut Cursor::new(file)).unwrap();
+        assert_eq!(info.duration, Some(90));
+    }
+}
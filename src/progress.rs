@@ -0,0 +1,142 @@
+// Imports
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+    time::SystemTime,
+};
+use tracing::warn;
+
+// share of the video after which it counts as watched, the credits are rarely sat through
+static WATCHED_RATIO: f64 = 0.9;
+
+/// Playback state of a single video for a viewer
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Progress {
+    // in seconds
+    pub position: f64,
+    pub duration: f64,
+    pub watched: bool,
+    // unix timestamp of the last update, orders the 'continue watching' row
+    pub updated: u64,
+}
+
+// viewer -> video key ('{card id}' or '{card id}/{season}/{episode}') -> progress
+type Entries = HashMap<String, HashMap<String, Progress>>;
+
+/// Watch progress of every viewer, persisted as json in the data directory
+#[derive(Clone)]
+pub struct ProgressStore {
+    fp: PathBuf,
+    entries: Arc<Mutex<Entries>>,
+}
+
+impl ProgressStore {
+    pub fn load() -> Self {
+        Self::load_from_file(crate::dirs::get().data_local_dir().join("progress.json"))
+    }
+
+    // a corrupt store is started over rather than preventing the server from starting
+    fn load_from_file(fp: PathBuf) -> Self {
+        let entries = match std::fs::read(&fp) {
+            Ok(bytes) => serde_json::from_slice(&bytes).unwrap_or_else(|err| {
+                warn!("Failed to parse '{}', '{err}'", fp.display());
+                Entries::default()
+            }),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Entries::default(),
+            Err(err) => {
+                warn!("Failed to read '{}', '{err}'", fp.display());
+                Entries::default()
+            }
+        };
+        Self {
+            fp,
+            entries: Arc::new(Mutex::new(entries)),
+        }
+    }
+
+    /// Progress of every video the viewer has started
+    pub fn get(&self, viewer: &str) -> HashMap<String, Progress> {
+        let entries = self.entries.lock().unwrap_or_else(|err| err.into_inner());
+        entries.get(viewer).cloned().unwrap_or_default()
+    }
+
+    /// Records the playback position of a video, marking it as watched once nearly finished
+    /// unless a watched flag is explicitly given
+    pub fn update(
+        &self,
+        viewer: &str,
+        key: &str,
+        position: f64,
+        duration: f64,
+        watched: Option<bool>,
+    ) -> eyre::Result<Progress> {
+        let mut entries = self.entries.lock().unwrap_or_else(|err| err.into_inner());
+        let progress = entries
+            .entry(viewer.to_owned())
+            .or_default()
+            .entry(key.to_owned())
+            .or_default();
+
+        progress.position = position.max(0.0);
+        progress.duration = duration.max(0.0);
+        progress.watched = watched.unwrap_or(
+            progress.watched
+                || (progress.duration > 0.0
+                    && progress.position >= progress.duration * WATCHED_RATIO),
+        );
+        progress.updated = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_secs());
+        let progress = progress.clone();
+
+        // written while locked so that concurrent updates can't be saved out of order
+        let bytes = serde_json::to_vec(&*entries)?;
+        if let Some(dir) = self.fp.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        crate::utils::write_atomic(&self.fp, &bytes)?;
+        Ok(progress)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn persisted_per_viewer() {
+        let dir = tempfile::tempdir().unwrap();
+        let fp = dir.path().join("progress.json");
+        let store = ProgressStore::load_from_file(fp.clone());
+
+        let progress = store
+            .update("user:alice", "abc", 60.0, 100.0, None)
+            .unwrap();
+        assert!(!progress.watched);
+        let progress = store
+            .update("user:alice", "abc", 95.0, 100.0, None)
+            .unwrap();
+        assert!(progress.watched);
+        // rewinding doesn't unwatch, only an explicit flag does
+        assert!(
+            store
+                .update("user:alice", "abc", 10.0, 100.0, None)
+                .unwrap()
+                .watched
+        );
+        assert!(
+            !store
+                .update("user:alice", "abc", 10.0, 100.0, Some(false))
+                .unwrap()
+                .watched
+        );
+        store.update("user:bob", "def/1/2", 5.0, 0.0, None).unwrap();
+
+        let reloaded = ProgressStore::load_from_file(fp.clone());
+        assert_eq!(reloaded.get("user:alice")["abc"].position, 10.0);
+        assert_eq!(reloaded.get("user:bob").len(), 1);
+        assert!(reloaded.get("user:carol").is_empty());
+    }
+}
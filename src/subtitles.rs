@@ -0,0 +1,148 @@
+// Conversion of sidecar subtitle files into WebVTT, the only format browsers can attach
+// to a <video> element
+
+// Imports
+use eyre::OptionExt;
+use itertools::Itertools;
+
+/// Extensions of the subtitle files that can be converted
+pub static EXTENSIONS: [&str; 4] = ["srt", "vtt", "ass", "ssa"];
+
+/// Converts the content of a subtitle file into WebVTT, based on its extension
+pub fn to_vtt(content: &str, extension: &str) -> eyre::Result<String> {
+    // editors commonly add a byte order mark and windows line endings
+    let content = content.trim_start_matches('\u{feff}').replace("\r\n", "\n");
+    match extension.to_ascii_lowercase().as_str() {
+        "vtt" => Ok(content),
+        "srt" => Ok(srt_to_vtt(&content)),
+        "ass" | "ssa" => ass_to_vtt(&content),
+        _ => Err(eyre::eyre!("Unsupported subtitle format '{extension}'")),
+    }
+}
+
+// SRT only differs by its missing header and the decimal comma of its timestamps
+fn srt_to_vtt(content: &str) -> String {
+    let mut output = String::from("WEBVTT\n\n");
+    for line in content.lines() {
+        if line.contains("-->") {
+            output.push_str(&line.replace(',', "."));
+        } else {
+            output.push_str(line);
+        }
+        output.push('\n');
+    }
+    output
+}
+
+// Only the timing and the text of the dialogue lines are kept, styling is dropped
+fn ass_to_vtt(content: &str) -> eyre::Result<String> {
+    let mut format: Option<Vec<String>> = None;
+    let mut in_events = false;
+    let mut cues: Vec<(String, String, String)> = Vec::new();
+
+    for line in content.lines().map(str::trim) {
+        if line.starts_with('[') {
+            in_events = line.eq_ignore_ascii_case("[events]");
+            continue;
+        }
+        if !in_events {
+            continue;
+        }
+        if let Some(fields) = line.strip_prefix("Format:") {
+            format = Some(
+                fields
+                    .split(',')
+                    .map(|field| field.trim().to_ascii_lowercase())
+                    .collect(),
+            );
+        } else if let Some(values) = line.strip_prefix("Dialogue:") {
+            let format = format
+                .as_ref()
+                .ok_or_eyre("Dialogue found before the format of the events")?;
+            let position = |name: &str| format.iter().position(|field| field == name);
+            let (Some(start), Some(end), Some(text)) =
+                (position("start"), position("end"), position("text"))
+            else {
+                return Err(eyre::eyre!("Format of the events lacks start, end or text"));
+            };
+            // the text is the last field and may itself contain commas
+            let values = values.trim().splitn(format.len(), ',').collect_vec();
+            let (Some(start), Some(end), Some(text)) =
+                (values.get(start), values.get(end), values.get(text))
+            else {
+                continue;
+            };
+            let (Some(start), Some(end)) = (ass_timestamp(start), ass_timestamp(end)) else {
+                continue;
+            };
+            cues.push((start, end, ass_text(text)));
+        }
+    }
+
+    // cues have to be ordered by start time, which the lexicographic order matches
+    let mut output = String::from("WEBVTT\n\n");
+    for (start, end, text) in cues.into_iter().sorted_by(|a, b| a.0.cmp(&b.0)) {
+        output.push_str(&format!("{start} --> {end}\n{text}\n\n"));
+    }
+    Ok(output)
+}
+
+// 'H:MM:SS.cc' into 'HH:MM:SS.mmm'
+fn ass_timestamp(timestamp: &str) -> Option<String> {
+    let (hours, rest) = timestamp.trim().split_once(':')?;
+    let (minutes, rest) = rest.split_once(':')?;
+    let (seconds, centiseconds) = rest.split_once('.')?;
+    let [hours, minutes, seconds, centiseconds] =
+        [hours, minutes, seconds, centiseconds].map(|part| part.parse::<u32>().ok());
+    Some(format!(
+        "{:0>2}:{:0>2}:{:0>2}.{:0>3}",
+        hours?,
+        minutes?,
+        seconds?,
+        centiseconds? * 10
+    ))
+}
+
+// Drops the override blocks such as '{\i1}' and converts the line breaks
+fn ass_text(text: &str) -> String {
+    let mut output = String::with_capacity(text.len());
+    let mut depth = 0_u32;
+    for chr in text.chars() {
+        match chr {
+            '{' => depth += 1,
+            '}' => depth = depth.saturating_sub(1),
+            _ if depth > 0 => (),
+            _ => output.push(chr),
+        }
+    }
+    output
+        .replace("\\N", "\n")
+        .replace("\\n", "\n")
+        .replace("\\h", " ")
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn srt() {
+        let srt = "\u{feff}1\r\n00:00:01,000 --> 00:00:02,500\r\nHello, world\r\n\r\n2\r\n00:01:00,000 --> 00:01:01,000\r\n<i>Bye</i>\r\n";
+        assert_eq!(
+            to_vtt(srt, "SRT").unwrap(),
+            "WEBVTT\n\n1\n00:00:01.000 --> 00:00:02.500\nHello, world\n\n2\n00:01:00.000 --> 00:01:01.000\n<i>Bye</i>\n"
+        );
+    }
+
+    #[test]
+    fn ass() {
+        let ass = "[Script Info]\nTitle: Test\n\n[V4+ Styles]\nFormat: Name, Fontname\nStyle: Default,Arial\n\n[Events]\nFormat: Layer, Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text\nDialogue: 0,0:00:05.10,0:00:06.00,Default,,0,0,0,,Second, line\nDialogue: 0,0:00:01.00,0:00:02.50,Default,,0,0,0,,{\\i1}First{\\i0}\\Nline\n";
+        assert_eq!(
+            to_vtt(ass, "ass").unwrap(),
+            "WEBVTT\n\n00:00:01.000 --> 00:00:02.500\nFirst\nline\n\n00:00:05.100 --> 00:00:06.000\nSecond, line\n\n"
+        );
+        assert!(to_vtt("[Events]\nDialogue: 0,0:00:01.00", "ssa").is_err());
+    }
+}
@@ -1,6 +1,10 @@
 // Imports
 use eyre::OptionExt;
-use std::{io::Read, path::Path};
+use std::{
+    io::{Read, Write},
+    path::Path,
+    sync::atomic::{AtomicU64, Ordering},
+};
 use time::{OffsetDateTime, UtcOffset};
 use tracing::{info, warn};
 
@@ -142,3 +146,71 @@ pub fn get_rel_path_string(path: &Path, base: &Path) -> Option<String> {
         }
     }
 }
+
+/// 64-bit FNV-1a hash, stable across runs and platforms unlike the std hasher
+pub fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325_u64, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x100000001b3)
+    })
+}
+
+/// Replaces the content of a file atomically, the data is written to a temporary file in the
+/// same directory, synced and then renamed over the target so readers never see a partial file
+pub fn write_atomic(fp: &Path, bytes: &[u8]) -> eyre::Result<()> {
+    // tells apart concurrent writes to the same file from within this process
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+    let dir = fp.parent().ok_or_eyre("File has no parent directory")?;
+    // the stem mustn't match any dot-file read by the cards while the write is in progress
+    let tmp_fp = dir.join(format!(
+        ".silvus-tmp-{}-{}-{}",
+        std::process::id(),
+        COUNTER.fetch_add(1, Ordering::Relaxed),
+        get_filename(fp)
+    ));
+
+    let mut file = std::fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&tmp_fp)?;
+    let mut write = || {
+        file.write_all(bytes)?;
+        file.sync_all()?;
+        std::fs::rename(&tmp_fp, fp)?;
+        Ok::<(), eyre::Error>(())
+    };
+    // only the temporary file created above is cleaned up
+    if let Err(err) = write() {
+        let _ = std::fs::remove_file(&tmp_fp);
+        return Err(err);
+    }
+
+    // persists the rename itself
+    #[cfg(unix)]
+    std::fs::File::open(dir)?.sync_all()?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn concurrent_atomic_writes() {
+        let dir = tempfile::tempdir().unwrap();
+        let fp = dir.path().join("cache");
+        std::fs::write(&fp, "old").unwrap();
+
+        std::thread::scope(|scope| {
+            for idx in 0..8 {
+                let fp = &fp;
+                scope.spawn(move || write_atomic(fp, format!("new {idx}").as_bytes()).unwrap());
+            }
+        });
+
+        assert!(std::fs::read_to_string(&fp).unwrap().starts_with("new "));
+        // no temporary file is left behind
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+}